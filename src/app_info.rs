@@ -2,13 +2,59 @@ use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
 pub struct AppInfo {
+    pub desktop_id: Option<String>, // e.g. "firefox.desktop" for applications
     pub name: String,
     pub description: String,
     pub exec: String,
     pub icon: Option<String>,
     pub categories: Vec<String>,
+    pub keywords: Vec<String>,
     pub item_type: ItemType,
     pub file_path: Option<PathBuf>, // Added for file handling
+    pub source_path: Option<PathBuf>, // .desktop file the entry was loaded from
+    pub terminal: bool, // Run inside a terminal emulator
+    pub working_dir: Option<PathBuf>,
+    pub actions: Vec<DesktopAction>, // [Desktop Action ...] groups listed in Actions=
+    pub mime_types: Vec<String>, // MIME types the application can open, from MimeType=
+    pub mime_type: Option<String>, // The type of the file, for files
+    pub payload: Option<String>, // Provider-specific data needed to activate the item
+}
+
+/// An additional way to start an application, e.g. "New Private Window"
+#[derive(Debug, Clone)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub exec: String,
+    pub icon: Option<String>,
+}
+
+impl AppInfo {
+    /// A launchable item for one of this application's desktop actions
+    pub fn action_item(&self, action: &DesktopAction) -> AppInfo {
+        AppInfo {
+            name: format!("{}: {}", self.name, action.name),
+            exec: action.exec.clone(),
+            icon: action.icon.clone().or_else(|| self.icon.clone()),
+            actions: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Stable key under which launches of this item are remembered
+    pub fn history_id(&self) -> Option<String> {
+        match self.item_type {
+            ItemType::Application => self.desktop_id.as_ref().map(|id| format!("app:{}", id)),
+            ItemType::RecentFile => self
+                .file_path
+                .as_ref()
+                .map(|path| format!("file:{}", path.display())),
+            ItemType::Command => Some(format!("command:{}", self.exec)),
+            ItemType::Plugin => self.payload.as_ref().map(|payload| format!("plugin:{}", payload)),
+            // Copying or trashing a file is not a use of it, nor is copying a result
+            ItemType::FileAction | ItemType::Calculator => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -16,10 +62,51 @@ pub enum ItemType {
     Application,
     Command,
     RecentFile,
+    Plugin,
+    FileAction, // "Show in folder", "Move to trash", ... for the file in file_path
+    Calculator, // A computed value in payload, copied on activation
 }
 
-#[derive(Debug)]
+/// One <bookmark> of recently-used.xbel. Timestamps are seconds since the Unix epoch.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct XbelBookmark {
-    pub file_path: PathBuf,
+    pub href: String, // URI of the file, percent-encoded
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub added: Option<u64>,
+    pub modified: Option<u64>,
+    pub visited: Option<u64>,
     pub mime_type: String,
+    pub groups: Vec<String>,
+    pub applications: Vec<XbelApplication>, // The applications that opened the file
+    pub private: bool, // Only the applications listed may show it
+    // Parts this launcher does not use, as XML, so rewriting the store keeps them
+    pub other_elements: Vec<String>, // Children of <bookmark>
+    pub other_metadata: Vec<String>, // <metadata> of other owners, in <info>
+    pub other_fields: Vec<String>,   // e.g. <bookmark:icon>, in the freedesktop <metadata>
+}
+
+/// An application registered on an XBEL bookmark
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct XbelApplication {
+    pub name: String,
+    pub exec: String, // e.g. "'gedit %u'", quoted as written by GLib
+    pub modified: Option<u64>,
+    pub count: u32, // How often it opened the file
+}
+
+impl XbelBookmark {
+    /// The local file this bookmark points to, if it is a file:// URI
+    pub fn file_path(&self) -> Option<PathBuf> {
+        crate::uri::file_path_from_uri(&self.href)
+    }
+
+    /// When the file was last used: the latest timestamp on the bookmark or its applications
+    pub fn last_used(&self) -> Option<u64> {
+        [self.added, self.modified, self.visited]
+            .into_iter()
+            .chain(self.applications.iter().map(|app| app.modified))
+            .flatten()
+            .max()
+    }
 }
\ No newline at end of file
@@ -1,18 +1,25 @@
 // app_launcher.rs - Enhanced with configurable quit functionality
-use super::{desktop_parser, file_loader, keyboard, search, styles, ui};
-use crate::app_info::AppInfo;
+use super::providers;
+use super::result_list::ResultList;
+use super::search_worker::{SearchBatch, SearchWorker};
+use super::watcher::ProviderWatcher;
+use super::{keyboard, search, styles, ui};
+use crate::history::LaunchHistory;
 use crate::settings::LauncherSettings;
 use gtk::prelude::GtkWindowExt;
 use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, Entry, ListBox};
-use std::collections::HashMap;
+use gtk::{Application, ApplicationWindow, Entry};
+use std::cell::RefCell;
 use std::process::Command;
+use std::rc::Rc;
+use tokio::sync::mpsc::UnboundedReceiver;
 pub struct AppLauncher {
-    apps: HashMap<String, AppInfo>,
-    recent_files: Vec<AppInfo>,
+    search_worker: Rc<SearchWorker>, // Runs the enabled providers off the main thread
+    history: Rc<RefCell<LaunchHistory>>, // Shared with the result rows, which record launches
     window: ApplicationWindow,
     search_entry: Entry,
-    app_list: ListBox,
+    results: ResultList,
+    watcher: Option<ProviderWatcher>, // Refreshes apps and files as they change on disk
     settings: LauncherSettings,
     app_ref: Option<Application>, // Store reference to quit/hide the app
 }
@@ -33,25 +40,38 @@ impl AppLauncher {
         window.set_modal(true);
         window.set_icon_name(Some("synaptrix"));
 
+        let (search_worker, search_results) =
+            SearchWorker::new(providers::build_providers(&settings))
+                .expect("Failed to start the search worker");
+
+        let history = LaunchHistory::load(
+            &LaunchHistory::default_path(),
+            settings.history.max_entries,
+        );
+
+        let history = Rc::new(RefCell::new(history));
+        let results = ResultList::new(history.clone(), &settings);
+
         let mut launcher = Self {
-            apps: HashMap::new(),
-            recent_files: Vec::new(),
+            search_worker: Rc::new(search_worker),
+            history,
             window,
             search_entry: Entry::new(),
-            app_list: ListBox::new(),
+            results,
+            watcher: None,
             settings,
             app_ref: Some(app.clone()),
         };
 
-        launcher.setup();
+        launcher.setup(search_results);
         launcher
     }
 
-    fn setup(&mut self) {
+    fn setup(&mut self, search_results: UnboundedReceiver<SearchBatch>) {
         ui::setup_ui(
             &self.window,
             &mut self.search_entry,
-            &mut self.app_list,
+            &self.results,
             &self.settings,
         );
         styles::setup_styles(&self.window, &self.settings);
@@ -62,23 +82,47 @@ impl AppLauncher {
         // Modified focus out handler to hide instead of close
         self.setup_hide_on_focus_out();
 
-        desktop_parser::load_applications(&mut self.apps);
-        file_loader::load_recent_files(&mut self.recent_files, &self.settings);
-        keyboard::setup_keyboard_navigation(&self.window, &self.search_entry, &self.app_list);
+        keyboard::setup_keyboard_navigation(&self.window, &self.search_entry, &self.results);
         search::setup_search(
             &self.search_entry,
-            &self.app_list,
-            &self.apps,
-            &self.recent_files,
+            &self.results,
+            &self.search_worker,
+            search_results,
+            &self.history,
             &self.settings,
         );
 
         // Setup enhanced keyboard handlers
         self.setup_keyboard_handlers();
 
+        self.setup_watcher();
+
         self.populate_list("");
     }
 
+    /// Pick up newly installed apps and new files without a restart
+    fn setup_watcher(&mut self) {
+        let window_weak = self.window.downgrade();
+        let entry_weak = self.search_entry.downgrade();
+        let worker_weak = Rc::downgrade(&self.search_worker);
+        let results = self.results.clone();
+
+        self.watcher = Some(ProviderWatcher::new(
+            self.search_worker.providers(),
+            move || {
+                // A hidden window searches again when shown; an open one updates in place
+                let (Some(window), Some(entry), Some(worker)) =
+                    (window_weak.upgrade(), entry_weak.upgrade(), worker_weak.upgrade())
+                else {
+                    return;
+                };
+                if window.is_visible() && !results.in_actions_mode() {
+                    worker.search(&entry.text().to_lowercase());
+                }
+            },
+        ));
+    }
+
     fn setup_close_handler(&self) {
         let app_weak = self.app_ref.as_ref().unwrap().downgrade();
         let quit_on_close = self.settings.behavior.quit_on_close;
@@ -188,25 +232,14 @@ impl AppLauncher {
     }
 
     fn populate_list(&mut self, query: &str) {
-        search::filter_and_populate(
-            &self.app_list,
-            &self.apps,
-            &self.recent_files,
-            query,
-            &self.settings,
-        );
+        // Results arrive asynchronously through the receiver set up in setup_search
+        self.search_worker.search(query);
     }
     pub fn show(&self) {
         // Clear search when showing
         self.search_entry.set_text("");
         // Re-populate the list with all items
-        search::filter_and_populate(
-            &self.app_list,
-            &self.apps,
-            &self.recent_files,
-            "",
-            &self.settings,
-        );
+        self.search_worker.search("");
         // Show and present the window
         self.window.set_visible(true);
         self.window.present();
@@ -216,6 +249,13 @@ impl AppLauncher {
         self.search_entry.grab_focus();
     }
 
+    /// Forget all remembered launches
+    pub fn clear_history(&self) {
+        if let Err(e) = self.history.borrow_mut().clear() {
+            eprintln!("Warning: Could not clear launch history: {}", e);
+        }
+    }
+
     fn center_window(&self) {
         // Only run on X11
         if std::env::var("XDG_SESSION_TYPE").unwrap_or_default() != "x11" {
@@ -0,0 +1,361 @@
+// desktop_entry.rs - Desktop Entry Specification parser
+//
+// Implements the generic key file format used by .desktop files:
+// groups, localized keys, value escapes and list-type values.
+// See https://specifications.freedesktop.org/desktop-entry-spec/latest/
+use std::collections::HashMap;
+
+/// The group holding the main entry of a .desktop file
+pub const MAIN_GROUP: &str = "Desktop Entry";
+
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    groups: Vec<Group>,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    name: String,
+    // Keys are stored including their locale suffix, e.g. "Name[de]"
+    entries: HashMap<String, String>,
+}
+
+/// A parsed POSIX locale (lang_COUNTRY.ENCODING@MODIFIER)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Locale {
+    pub lang: String,
+    pub country: Option<String>,
+    pub modifier: Option<String>,
+}
+
+impl Locale {
+    /// Parse a locale string such as "sr_YU.UTF-8@Latn". The encoding is ignored.
+    pub fn parse(value: &str) -> Option<Self> {
+        let (rest, modifier) = match value.split_once('@') {
+            Some((rest, modifier)) => (rest, Some(modifier.to_string())),
+            None => (value, None),
+        };
+        let rest = rest.split('.').next().unwrap_or(rest);
+        let (lang, country) = match rest.split_once('_') {
+            Some((lang, country)) => (lang, Some(country.to_string())),
+            None => (rest, None),
+        };
+
+        if lang.is_empty() || lang == "C" || lang == "POSIX" {
+            return None;
+        }
+
+        Some(Self {
+            lang: lang.to_string(),
+            country,
+            modifier,
+        })
+    }
+
+    /// Determine the message locale from LC_ALL, LC_MESSAGES and LANG, in that order
+    pub fn from_env() -> Option<Self> {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+    }
+
+    /// Locale suffixes to try for a localized key, most specific first
+    pub fn candidates(&self) -> Vec<String> {
+        let mut candidates = Vec::new();
+        if let (Some(country), Some(modifier)) = (&self.country, &self.modifier) {
+            candidates.push(format!("{}_{}@{}", self.lang, country, modifier));
+        }
+        if let Some(country) = &self.country {
+            candidates.push(format!("{}_{}", self.lang, country));
+        }
+        if let Some(modifier) = &self.modifier {
+            candidates.push(format!("{}@{}", self.lang, modifier));
+        }
+        candidates.push(self.lang.clone());
+        candidates
+    }
+}
+
+impl DesktopEntry {
+    /// Parse the contents of a desktop entry file. Malformed lines are skipped,
+    /// and for repeated groups or keys the first occurrence wins.
+    pub fn parse(content: &str) -> Self {
+        let mut groups: Vec<Group> = Vec::new();
+        let mut current: Option<usize> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let name = &line[1..line.len() - 1];
+                current = match groups.iter().position(|g| g.name == name) {
+                    // Duplicate groups are invalid; ignore the later one
+                    Some(_) => None,
+                    None => {
+                        groups.push(Group {
+                            name: name.to_string(),
+                            entries: HashMap::new(),
+                        });
+                        Some(groups.len() - 1)
+                    }
+                };
+                continue;
+            }
+
+            // Key-value pairs before the first group header are not allowed
+            let Some(index) = current else { continue };
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim_end();
+            if !is_valid_key(key) {
+                continue;
+            }
+
+            groups[index]
+                .entries
+                .entry(key.to_string())
+                .or_insert_with(|| value.trim_start().to_string());
+        }
+
+        Self { groups }
+    }
+
+    pub fn has_group(&self, group: &str) -> bool {
+        self.group(group).is_some()
+    }
+
+    /// Names of all groups in file order
+    pub fn group_names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|g| g.name.as_str())
+    }
+
+    fn group(&self, group: &str) -> Option<&Group> {
+        self.groups.iter().find(|g| g.name == group)
+    }
+
+    /// The raw, unescaped value of a key
+    pub fn raw(&self, group: &str, key: &str) -> Option<&str> {
+        self.group(group)?.entries.get(key).map(|v| v.as_str())
+    }
+
+    fn raw_localized(&self, group: &str, key: &str, locale: Option<&Locale>) -> Option<&str> {
+        let entries = &self.group(group)?.entries;
+        if let Some(locale) = locale {
+            for suffix in locale.candidates() {
+                if let Some(value) = entries.get(&format!("{}[{}]", key, suffix)) {
+                    return Some(value);
+                }
+            }
+        }
+        entries.get(key).map(|v| v.as_str())
+    }
+
+    /// A `string` value with escape sequences resolved
+    pub fn string(&self, group: &str, key: &str) -> Option<String> {
+        self.raw(group, key).map(unescape)
+    }
+
+    /// A `localestring` value, picking the best match for `locale`
+    pub fn locale_string(&self, group: &str, key: &str, locale: Option<&Locale>) -> Option<String> {
+        self.raw_localized(group, key, locale).map(unescape)
+    }
+
+    /// A `boolean` value; anything other than "true" or "false" is treated as absent
+    pub fn boolean(&self, group: &str, key: &str) -> Option<bool> {
+        match self.raw(group, key)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// A list of `string` values separated by semicolons
+    pub fn strings(&self, group: &str, key: &str) -> Vec<String> {
+        self.raw(group, key).map(split_list).unwrap_or_default()
+    }
+
+    /// A list of `localestring` values separated by semicolons
+    pub fn locale_strings(&self, group: &str, key: &str, locale: Option<&Locale>) -> Vec<String> {
+        self.raw_localized(group, key, locale)
+            .map(split_list)
+            .unwrap_or_default()
+    }
+}
+
+/// Keys consist of A-Za-z0-9- optionally followed by a [locale] suffix
+fn is_valid_key(key: &str) -> bool {
+    let base = match key.find('[') {
+        Some(start) => {
+            if !key.ends_with(']') || start + 2 > key.len() - 1 {
+                return false;
+            }
+            &key[..start]
+        }
+        None => key,
+    };
+    !base.is_empty() && base.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Resolve the escape sequences \s, \n, \t, \r and \\ in a value
+pub fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            // Unknown escapes are kept verbatim
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Split a list value on unescaped semicolons, resolving `\;` and the usual escapes
+pub fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ';' => items.push(unescape(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        items.push(unescape(&current));
+    }
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_parsing() {
+        let locale = Locale::parse("sr_YU.UTF-8@Latn").unwrap();
+        assert_eq!(locale.lang, "sr");
+        assert_eq!(locale.country.as_deref(), Some("YU"));
+        assert_eq!(locale.modifier.as_deref(), Some("Latn"));
+        assert_eq!(
+            locale.candidates(),
+            vec!["sr_YU@Latn", "sr_YU", "sr@Latn", "sr"]
+        );
+
+        assert!(Locale::parse("C").is_none());
+        assert!(Locale::parse("POSIX").is_none());
+        assert!(Locale::parse("C.UTF-8").is_none());
+        assert_eq!(Locale::parse("de").unwrap().candidates(), vec!["de"]);
+    }
+
+    #[test]
+    fn test_groups_are_separate() {
+        let entry = DesktopEntry::parse(
+            "# comment\n[Desktop Entry]\nName=Main\nExec=main\n\n[Desktop Action new]\nName=Action\nExec=main --new\n",
+        );
+        assert_eq!(entry.string(MAIN_GROUP, "Name").as_deref(), Some("Main"));
+        assert_eq!(entry.string(MAIN_GROUP, "Exec").as_deref(), Some("main"));
+        assert_eq!(
+            entry.string("Desktop Action new", "Exec").as_deref(),
+            Some("main --new")
+        );
+        assert_eq!(
+            entry.group_names().collect::<Vec<_>>(),
+            vec![MAIN_GROUP, "Desktop Action new"]
+        );
+    }
+
+    #[test]
+    fn test_first_occurrence_wins() {
+        let entry = DesktopEntry::parse(
+            "[Desktop Entry]\nName=First\nName=Second\n[Desktop Entry]\nName=Third\n",
+        );
+        assert_eq!(entry.string(MAIN_GROUP, "Name").as_deref(), Some("First"));
+    }
+
+    #[test]
+    fn test_whitespace_around_equals() {
+        let entry = DesktopEntry::parse("[Desktop Entry]\nName = Spaced Out\n");
+        assert_eq!(entry.string(MAIN_GROUP, "Name").as_deref(), Some("Spaced Out"));
+    }
+
+    #[test]
+    fn test_invalid_lines_are_ignored() {
+        let entry = DesktopEntry::parse(
+            "Name=Orphan\n[Desktop Entry]\nnot a key value\nBad_Key=1\nName[]=x\nName=Ok\n",
+        );
+        assert_eq!(entry.string(MAIN_GROUP, "Name").as_deref(), Some("Ok"));
+        assert!(entry.raw(MAIN_GROUP, "Bad_Key").is_none());
+    }
+
+    #[test]
+    fn test_escapes() {
+        assert_eq!(unescape(r"a\sb\nc\td\re\\f"), "a b\nc\td\re\\f");
+        assert_eq!(unescape(r#"keep \" and \$"#), r#"keep \" and \$"#);
+    }
+
+    #[test]
+    fn test_lists() {
+        let entry = DesktopEntry::parse(
+            "[Desktop Entry]\nCategories=GTK;Utility;;\nKeywords=semi\\;colon;two\\swords\n",
+        );
+        assert_eq!(entry.strings(MAIN_GROUP, "Categories"), vec!["GTK", "Utility"]);
+        assert_eq!(entry.strings(MAIN_GROUP, "Keywords"), vec!["semi;colon", "two words"]);
+        assert!(entry.strings(MAIN_GROUP, "Missing").is_empty());
+    }
+
+    #[test]
+    fn test_localized_lookup() {
+        let entry = DesktopEntry::parse(
+            "[Desktop Entry]\nName=Default\nName[sr]=Lang\nName[sr@Latn]=Modifier\nName[sr_YU]=Country\n",
+        );
+        let lookup = |locale: &str| {
+            entry
+                .locale_string(MAIN_GROUP, "Name", Locale::parse(locale).as_ref())
+                .unwrap()
+        };
+        assert_eq!(lookup("sr_YU@Latn"), "Country");
+        assert_eq!(lookup("sr_CS@Latn"), "Modifier");
+        assert_eq!(lookup("sr_CS"), "Lang");
+        assert_eq!(lookup("de_DE.UTF-8"), "Default");
+        assert_eq!(
+            entry.locale_string(MAIN_GROUP, "Name", None).as_deref(),
+            Some("Default")
+        );
+    }
+
+    #[test]
+    fn test_booleans() {
+        let entry = DesktopEntry::parse("[Desktop Entry]\nNoDisplay=true\nHidden=false\nTerminal=yes\n");
+        assert_eq!(entry.boolean(MAIN_GROUP, "NoDisplay"), Some(true));
+        assert_eq!(entry.boolean(MAIN_GROUP, "Hidden"), Some(false));
+        assert_eq!(entry.boolean(MAIN_GROUP, "Terminal"), None);
+    }
+}
@@ -1,88 +1,449 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::Path;
-
-use crate::app_info::{AppInfo, ItemType};
-
-pub fn load_applications(apps: &mut HashMap<String, AppInfo>) {
-    let home_path = format!(
-        "{}/.local/share/applications",
-        std::env::var("HOME").unwrap_or_default()
-    );
-    let desktop_dirs = vec![
-        "/usr/share/applications",
-        "/usr/local/share/applications",
-        &home_path,
-    ];
-
-    for dir in desktop_dirs {
-        if let Ok(entries) = fs::read_dir(dir) {
-            for entry in entries.flatten() {
-                if let Some(ext) = entry.path().extension() {
-                    if ext == "desktop" {
-                        if let Ok(app_info) = parse_desktop_file(&entry.path()) {
-                            apps.insert(app_info.name.clone(), app_info);
-                        }
-                    }
-                }
+use std::path::{Path, PathBuf};
+
+use super::desktop_entry::{DesktopEntry, Locale, MAIN_GROUP};
+use crate::app_info::{AppInfo, DesktopAction, ItemType};
+use crate::settings::LauncherSettings;
+use crate::spawner::find_executable;
+use crate::xdg;
+
+/// Load all applications into `apps`, keyed by desktop-file ID
+pub fn load_applications(apps: &mut HashMap<String, AppInfo>, settings: &LauncherSettings) {
+    let desktops = desktop_filter(settings);
+    load_applications_from(apps, &xdg::data_search_path(), desktops.as_deref());
+}
+
+/// The desktops to filter OnlyShowIn/NotShowIn against, or None to show everything
+pub fn desktop_filter(settings: &LauncherSettings) -> Option<Vec<String>> {
+    if settings.behavior.show_all_desktops {
+        None
+    } else {
+        Some(xdg::current_desktops())
+    }
+}
+
+/// Load applications from the `applications` subdirectory of each data directory.
+/// `data_dirs` is ordered by precedence, so an entry whose desktop-file ID was
+/// already seen in an earlier directory is shadowed, even if that earlier entry
+/// turned out to be hidden or invalid.
+pub fn load_applications_from(
+    apps: &mut HashMap<String, AppInfo>,
+    data_dirs: &[PathBuf],
+    desktops: Option<&[String]>,
+) {
+    for (id, path) in resolve_desktop_files(data_dirs) {
+        if let Ok(mut app_info) = parse_desktop_file(&path, desktops) {
+            app_info.desktop_id = Some(id.clone());
+            apps.insert(id, app_info);
+        }
+    }
+}
+
+/// The .desktop file that defines each desktop-file ID: the one in the
+/// earliest of `data_dirs`
+pub fn resolve_desktop_files(data_dirs: &[PathBuf]) -> Vec<(String, PathBuf)> {
+    let mut seen_ids = HashSet::new();
+    let mut files = Vec::new();
+
+    for data_dir in data_dirs {
+        for (id, path) in scan_applications_dir(&data_dir.join("applications")) {
+            if seen_ids.insert(id.clone()) {
+                files.push((id, path));
             }
         }
     }
+    files
 }
 
-pub fn parse_desktop_file(path: &Path) -> Result<AppInfo, Box<dyn std::error::Error>> {
-    let content = fs::read_to_string(path)?;
-    let mut name = String::new();
-    let mut description = String::new();
-    let mut exec = String::new();
-    let mut icon = None;
-    let mut categories = Vec::new();
-    let mut no_display = false;
-    let mut hidden = false;
-
-    for line in content.lines() {
-        let line = line.trim();
-        if line.starts_with("Name=") && name.is_empty() {
-            name = line[5..].to_string();
-        } else if line.starts_with("Comment=") && description.is_empty() {
-            description = line[8..].to_string();
-        } else if line.starts_with("Exec=") {
-            exec = line[5..].to_string();
-        } else if line.starts_with("Icon=") {
-            if icon.is_none() {
-                icon = Some(line[5..].to_string());
+/// Every `applications` directory and its subdirectories, including
+/// `applications` directories that do not exist yet
+pub fn applications_dirs(data_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    for data_dir in data_dirs {
+        collect_dirs(&data_dir.join("applications"), &mut dirs);
+    }
+    dirs
+}
+
+fn collect_dirs(dir: &Path, dirs: &mut Vec<PathBuf>) {
+    dirs.push(dir.to_path_buf());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false) {
+            collect_dirs(&entry.path(), dirs);
+        }
+    }
+}
+
+/// Recursively collect the .desktop files below `root` along with their desktop-file IDs
+pub fn scan_applications_dir(root: &Path) -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    collect_desktop_files(root, root, &mut files);
+    // Directory iteration order is unspecified; keep results stable
+    files.sort();
+    files
+}
+
+fn collect_desktop_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+        if is_dir {
+            collect_desktop_files(root, &path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            if let Some(id) = desktop_file_id(root, &path) {
+                files.push((id, path));
             }
-        } else if line.starts_with("Categories=") {
-            categories = line[11..]
-                .split(';')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.to_string())
-                .collect();
-        } else if line == "NoDisplay=true" {
-            no_display = true;
-        } else if line == "Hidden=true" {
-            hidden = true;
         }
     }
+}
+
+/// The desktop-file ID is the path relative to the applications directory with
+/// `/` replaced by `-`, so applications/kde4/foo.desktop becomes kde4-foo.desktop
+pub fn desktop_file_id(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    Some(parts.join("-"))
+}
+
+/// Parse a .desktop file, rejecting entries not meant for one of `desktops`
+pub fn parse_desktop_file(
+    path: &Path,
+    desktops: Option<&[String]>,
+) -> Result<AppInfo, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)?;
+    let entry = DesktopEntry::parse(&content);
+    if let Some(desktops) = desktops {
+        if !is_shown_in(&entry, desktops) {
+            return Err("Entry is not shown in the current desktop".into());
+        }
+    }
+    let mut app_info = app_info_from_entry(&entry, Locale::from_env().as_ref())?;
+    app_info.source_path = Some(path.to_path_buf());
+    Ok(app_info)
+}
+
+/// Apply OnlyShowIn/NotShowIn to the desktops named in XDG_CURRENT_DESKTOP.
+/// As in GLib, OnlyShowIn entries are hidden when no desktop is known.
+pub fn is_shown_in(entry: &DesktopEntry, desktops: &[String]) -> bool {
+    let only_show_in = entry.strings(MAIN_GROUP, "OnlyShowIn");
+    if !only_show_in.is_empty() && !only_show_in.iter().any(|d| desktops.contains(d)) {
+        return false;
+    }
+
+    let not_show_in = entry.strings(MAIN_GROUP, "NotShowIn");
+    !not_show_in.iter().any(|d| desktops.contains(d))
+}
+
+/// Build an AppInfo from the [Desktop Entry] group, using `locale` for localized keys
+pub fn app_info_from_entry(
+    entry: &DesktopEntry,
+    locale: Option<&Locale>,
+) -> Result<AppInfo, Box<dyn std::error::Error>> {
+    if !entry.has_group(MAIN_GROUP) {
+        return Err("Missing [Desktop Entry] group".into());
+    }
+
+    // Links and directories are not launchable applications
+    if entry.string(MAIN_GROUP, "Type").as_deref() != Some("Application") {
+        return Err("Not an application entry".into());
+    }
+
+    let name = entry
+        .locale_string(MAIN_GROUP, "Name", locale)
+        .unwrap_or_default();
+    let exec = entry.string(MAIN_GROUP, "Exec").unwrap_or_default();
+    let no_display = entry.boolean(MAIN_GROUP, "NoDisplay").unwrap_or(false);
+    let hidden = entry.boolean(MAIN_GROUP, "Hidden").unwrap_or(false);
 
     if name.is_empty() || exec.is_empty() || no_display || hidden {
         return Err("Invalid or hidden desktop entry".into());
     }
 
-    // Clean up exec command
-    exec = exec
-        .split_whitespace()
-        .filter(|s| !s.starts_with('%'))
-        .collect::<Vec<_>>()
-        .join(" ");
+    // TryExec names a binary that must be installed for the entry to be usable
+    if let Some(try_exec) = entry.string(MAIN_GROUP, "TryExec").filter(|t| !t.is_empty()) {
+        if find_executable(&try_exec).is_none() {
+            return Err(format!("TryExec binary {} not found", try_exec).into());
+        }
+    }
+
+    // Fall back to the generic name ("Web Browser") when there is no comment
+    let description = entry
+        .locale_string(MAIN_GROUP, "Comment", locale)
+        .or_else(|| entry.locale_string(MAIN_GROUP, "GenericName", locale))
+        .unwrap_or_default();
 
     Ok(AppInfo {
+        desktop_id: None,
         name,
         description,
         exec,
-        icon,
-        categories,
+        icon: entry.string(MAIN_GROUP, "Icon").filter(|icon| !icon.is_empty()),
+        categories: entry.strings(MAIN_GROUP, "Categories"),
+        keywords: entry.locale_strings(MAIN_GROUP, "Keywords", locale),
         item_type: ItemType::Application,
         file_path: None,
+        source_path: None,
+        terminal: entry.boolean(MAIN_GROUP, "Terminal").unwrap_or(false),
+        working_dir: entry
+            .string(MAIN_GROUP, "Path")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+        actions: parse_actions(entry, locale),
+        mime_types: entry.strings(MAIN_GROUP, "MimeType"),
+        mime_type: None,
+        payload: None,
     })
-}
\ No newline at end of file
+}
+
+/// Collect the actions listed in Actions= that have a matching [Desktop Action id] group
+fn parse_actions(entry: &DesktopEntry, locale: Option<&Locale>) -> Vec<DesktopAction> {
+    entry
+        .strings(MAIN_GROUP, "Actions")
+        .into_iter()
+        .filter_map(|id| {
+            let group = format!("Desktop Action {}", id);
+            let name = entry.locale_string(&group, "Name", locale)?;
+            // Actions without Exec are only usable through D-Bus activation
+            let exec = entry.string(&group, "Exec").filter(|exec| !exec.is_empty())?;
+            Some(DesktopAction {
+                icon: entry.string(&group, "Icon").filter(|icon| !icon.is_empty()),
+                id,
+                name,
+                exec,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/desktop")
+            .join(name)
+    }
+
+    fn parse_fixture(name: &str, locale: &str) -> Result<AppInfo, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(fixture_path(name)).unwrap();
+        app_info_from_entry(&DesktopEntry::parse(&content), Locale::parse(locale).as_ref())
+    }
+
+    #[test]
+    fn test_main_group_not_overwritten_by_actions() {
+        let app = parse_fixture("firefox.desktop", "C").unwrap();
+        assert_eq!(app.name, "Firefox Web Browser");
+        assert_eq!(app.description, "Browse the World Wide Web");
+        assert_eq!(app.exec, "firefox %u");
+        assert_eq!(app.icon.as_deref(), Some("firefox"));
+        assert_eq!(app.categories, vec!["GNOME", "GTK", "Network", "WebBrowser"]);
+        assert!(app.keywords.contains(&"Explorer".to_string()));
+        assert_eq!(app.mime_types.len(), 5);
+        assert_eq!(app.mime_types[0], "text/html");
+    }
+
+    #[test]
+    fn test_desktop_actions() {
+        let app = parse_fixture("firefox.desktop", "de_DE.UTF-8").unwrap();
+        let actions: Vec<_> = app
+            .actions
+            .iter()
+            .map(|a| (a.id.as_str(), a.name.as_str(), a.exec.as_str()))
+            .collect();
+        assert_eq!(
+            actions,
+            vec![
+                ("new-window", "Ein neues Fenster öffnen", "firefox -new-window"),
+                ("new-private-window", "Ein neues privates Fenster öffnen", "firefox -private-window"),
+            ]
+        );
+
+        let private = app.action_item(&app.actions[1]);
+        assert_eq!(private.name, "Firefox-Webbrowser: Ein neues privates Fenster öffnen");
+        assert_eq!(private.exec, "firefox -private-window");
+        assert_eq!(private.icon.as_deref(), Some("firefox"));
+        assert!(private.actions.is_empty());
+    }
+
+    #[test]
+    fn test_undeclared_action_groups_are_ignored() {
+        let entry = DesktopEntry::parse(
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nActions=listed;missing;\n\n[Desktop Action listed]\nName=Listed\nExec=app --listed\n\n[Desktop Action unlisted]\nName=Unlisted\nExec=app --unlisted\n",
+        );
+        let app = app_info_from_entry(&entry, None).unwrap();
+        assert_eq!(app.actions.len(), 1);
+        assert_eq!(app.actions[0].id, "listed");
+    }
+
+    #[test]
+    fn test_localized_keys() {
+        let app = parse_fixture("firefox.desktop", "de_DE.UTF-8").unwrap();
+        assert_eq!(app.name, "Firefox-Webbrowser");
+        assert_eq!(app.description, "Im Internet surfen");
+        assert!(app.keywords.contains(&"Surfen".to_string()));
+
+        let app = parse_fixture("firefox.desktop", "pt_BR.UTF-8").unwrap();
+        assert_eq!(app.name, "Navegador Web Firefox");
+        // No Portuguese comment, so the default is used
+        assert_eq!(app.description, "Browse the World Wide Web");
+    }
+
+    #[test]
+    fn test_escaped_values() {
+        let app = parse_fixture("escapes.desktop", "C").unwrap();
+        assert_eq!(app.name, "Escaped App");
+        assert_eq!(app.description, "First line\nSecond line");
+        assert_eq!(app.exec, r#""/opt/My App/bin/app" --title "Say \"hi\"" %F"#);
+        assert_eq!(app.icon.as_deref(), Some("/opt/My App/share/icon.png"));
+        assert_eq!(app.categories, vec!["Utility", "Semi;Colon"]);
+    }
+
+    #[test]
+    fn test_rejected_entries() {
+        assert!(parse_fixture("hidden.desktop", "C").is_err());
+        assert!(parse_fixture("link.desktop", "C").is_err());
+        assert!(parse_fixture("no-exec.desktop", "C").is_err());
+    }
+
+    #[test]
+    fn test_terminal_path_and_try_exec() {
+        let app = parse_fixture("htop.desktop", "C").unwrap();
+        assert!(app.terminal);
+        assert_eq!(app.working_dir.as_deref(), Some(Path::new("/tmp")));
+
+        let app = parse_fixture("firefox.desktop", "C").unwrap();
+        assert!(!app.terminal);
+        assert!(app.working_dir.is_none());
+
+        assert!(parse_fixture("stale.desktop", "C").is_err());
+    }
+
+    #[test]
+    fn test_parse_desktop_file_reads_from_disk() {
+        let app = parse_desktop_file(&fixture_path("hidden.desktop"), None);
+        assert!(app.is_err());
+        assert!(parse_desktop_file(&fixture_path("does-not-exist.desktop"), None).is_err());
+    }
+
+    fn write_entry(dir: &Path, relative: &str, name: &str, extra: &str) {
+        let path = dir.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!("[Desktop Entry]\nType=Application\nName={}\nExec=true\n{}", name, extra),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_desktop_file_ids() {
+        let root = Path::new("/usr/share/applications");
+        assert_eq!(
+            desktop_file_id(root, &root.join("firefox.desktop")).as_deref(),
+            Some("firefox.desktop")
+        );
+        assert_eq!(
+            desktop_file_id(root, &root.join("kde4/foo.desktop")).as_deref(),
+            Some("kde4-foo.desktop")
+        );
+        assert!(desktop_file_id(root, Path::new("/elsewhere/foo.desktop")).is_none());
+    }
+
+    #[test]
+    fn test_user_entries_shadow_system_entries() {
+        let user = tempdir().unwrap();
+        let system = tempdir().unwrap();
+        let user_apps = user.path().join("applications");
+        let system_apps = system.path().join("applications");
+
+        write_entry(&user_apps, "editor.desktop", "User Editor", "");
+        write_entry(&system_apps, "editor.desktop", "System Editor", "");
+        // A hidden user entry must hide the system entry with the same ID
+        write_entry(&user_apps, "kde4-viewer.desktop", "User Viewer", "Hidden=true\n");
+        write_entry(&system_apps, "kde4/viewer.desktop", "System Viewer", "");
+        write_entry(&system_apps, "kde4/other.desktop", "Other", "");
+        write_entry(&system_apps, "notes.txt", "Not A Desktop File", "");
+
+        let mut apps = HashMap::new();
+        load_applications_from(
+            &mut apps,
+            &[user.path().to_path_buf(), system.path().to_path_buf()],
+            None,
+        );
+
+        let mut ids: Vec<_> = apps.keys().cloned().collect();
+        ids.sort();
+        assert_eq!(ids, vec!["editor.desktop", "kde4-other.desktop"]);
+
+        let editor = &apps["editor.desktop"];
+        assert_eq!(editor.name, "User Editor");
+        assert_eq!(editor.desktop_id.as_deref(), Some("editor.desktop"));
+        assert_eq!(
+            editor.source_path.as_deref(),
+            Some(user_apps.join("editor.desktop").as_path())
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names_are_kept() {
+        let system = tempdir().unwrap();
+        let apps_dir = system.path().join("applications");
+        write_entry(&apps_dir, "gnome-terminal.desktop", "Terminal", "");
+        write_entry(&apps_dir, "xfce4-terminal.desktop", "Terminal", "");
+
+        let mut apps = HashMap::new();
+        load_applications_from(&mut apps, &[system.path().to_path_buf()], None);
+
+        assert_eq!(apps.len(), 2);
+        assert!(apps.values().all(|app| app.name == "Terminal"));
+    }
+
+    #[test]
+    fn test_only_show_in_and_not_show_in() {
+        let only_gnome = DesktopEntry::parse("[Desktop Entry]\nOnlyShowIn=GNOME;Unity;\n");
+        let not_kde = DesktopEntry::parse("[Desktop Entry]\nNotShowIn=KDE;\n");
+        let everywhere = DesktopEntry::parse("[Desktop Entry]\nName=Any\n");
+        let desktops = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+
+        assert!(is_shown_in(&only_gnome, &desktops(&["ubuntu", "GNOME"])));
+        assert!(!is_shown_in(&only_gnome, &desktops(&["X-Cinnamon"])));
+        assert!(!is_shown_in(&only_gnome, &[]));
+
+        assert!(!is_shown_in(&not_kde, &desktops(&["KDE"])));
+        assert!(is_shown_in(&not_kde, &desktops(&["X-Cinnamon"])));
+        assert!(is_shown_in(&not_kde, &[]));
+
+        assert!(is_shown_in(&everywhere, &desktops(&["X-Cinnamon"])));
+    }
+
+    #[test]
+    fn test_desktop_filter_while_loading() {
+        let system = tempdir().unwrap();
+        let apps_dir = system.path().join("applications");
+        write_entry(&apps_dir, "gnome-panel.desktop", "GNOME Panel", "OnlyShowIn=GNOME;\n");
+        write_entry(&apps_dir, "cinnamon-panel.desktop", "Cinnamon Panel", "OnlyShowIn=X-Cinnamon;\n");
+
+        let cinnamon = vec!["X-Cinnamon".to_string()];
+        let mut apps = HashMap::new();
+        load_applications_from(&mut apps, &[system.path().to_path_buf()], Some(&cinnamon));
+        assert_eq!(apps.keys().collect::<Vec<_>>(), vec!["cinnamon-panel.desktop"]);
+
+        // No filter shows every entry
+        let mut apps = HashMap::new();
+        load_applications_from(&mut apps, &[system.path().to_path_buf()], None);
+        assert_eq!(apps.len(), 2);
+    }
+}
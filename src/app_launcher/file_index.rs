@@ -0,0 +1,691 @@
+// file_index.rs - Recursive index of the configured file directories
+//
+// The index remembers every directory it walked together with its modification
+// time. On the next start only directories whose modification time changed are
+// read again, so an unchanged tree costs one stat per directory instead of a
+// full walk. Files edited in place keep their old timestamp until their
+// directory changes, which only affects the newest-first ordering.
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::settings::RecentFilesSettings;
+use crate::xdg;
+
+// A directory modified this recently may change again without its modification
+// time moving (timestamps are coarse), so it is not trusted on the next refresh
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// What to index; a saved index built with different options is thrown away
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexOptions {
+    pub roots: Vec<PathBuf>,
+    pub max_depth: usize,
+    pub show_hidden: bool,
+    pub use_ignore_files: bool,
+    pub exclude: Vec<String>,
+}
+
+impl IndexOptions {
+    pub fn from_settings(settings: &RecentFilesSettings) -> Self {
+        Self {
+            roots: settings.directories.iter().map(PathBuf::from).collect(),
+            max_depth: settings.max_depth,
+            show_hidden: settings.show_hidden,
+            use_ignore_files: settings.use_ignore_files,
+            exclude: settings.exclude.clone(),
+        }
+    }
+}
+
+/// One line of a .gitignore (or an exclude pattern from the settings)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IgnoreRule {
+    base: PathBuf,   // Directory the pattern is relative to
+    pattern: String, // Glob, without the leading '!' and trailing '/'
+    negated: bool,   // "!pattern" re-includes what an earlier rule excluded
+    dir_only: bool,  // "pattern/" only matches directories
+    anchored: bool,  // Patterns containing '/' match the path below `base`, others any name
+}
+
+impl IgnoreRule {
+    /// Parse a line in .gitignore syntax; blank lines and comments give None
+    pub fn parse(line: &str, base: &Path) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = line.trim_start_matches('/');
+        if pattern.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            base: base.to_path_buf(),
+            pattern: pattern.to_string(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// A rule hiding exactly `name` in `base`, as listed in a .hidden file
+    fn hidden_name(name: &str, base: &Path) -> Self {
+        let mut pattern = String::new();
+        for c in name.chars() {
+            if matches!(c, '*' | '?' | '[' | '\\') {
+                pattern.push('\\');
+            }
+            pattern.push(c);
+        }
+        Self {
+            base: base.to_path_buf(),
+            pattern,
+            negated: false,
+            dir_only: false,
+            anchored: true,
+        }
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        let text = if self.anchored {
+            relative.to_string_lossy()
+        } else {
+            match relative.file_name() {
+                Some(name) => name.to_string_lossy(),
+                None => return false,
+            }
+        };
+        glob_match(&self.pattern, &text)
+    }
+}
+
+/// Whether `path` is excluded; as in git, the last matching rule decides
+pub fn is_ignored(path: &Path, is_dir: bool, rules: &[IgnoreRule]) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(path, is_dir))
+        .map(|rule| !rule.negated)
+        .unwrap_or(false)
+}
+
+/// Match `text` against a glob: `*` and `?` stop at '/', `**` crosses directories,
+/// `[a-z]` / `[!a-z]` are character classes and `\` escapes the next character
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            match rest.first() {
+                // Trailing "**" matches everything below
+                None => true,
+                // "**/" matches zero or more whole directories
+                Some('/') => {
+                    let rest = &rest[1..];
+                    (0..=text.len())
+                        .filter(|&i| i == 0 || text[i - 1] == '/')
+                        .any(|i| glob_match_chars(rest, &text[i..]))
+                }
+                _ => (0..=text.len()).any(|i| glob_match_chars(rest, &text[i..])),
+            }
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match_chars(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            matches!(text.first(), Some(c) if *c != '/')
+                && glob_match_chars(&pattern[1..], &text[1..])
+        }
+        Some('[') => match (
+            text.first(),
+            match_class(&pattern[1..], text.first().copied()),
+        ) {
+            (Some(_), Some((true, len))) => glob_match_chars(&pattern[1 + len..], &text[1..]),
+            (_, Some((false, _))) | (None, _) => false,
+            // An unclosed '[' is an ordinary character
+            (Some(c), None) => *c == '[' && glob_match_chars(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match_chars(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match_chars(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Match `c` against the class starting after '['. Returns whether it matched and
+/// how many pattern characters the class used, or None if the class is not closed.
+fn match_class(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let (negated, mut i) = match class.first() {
+        Some('!') | Some('^') => (true, 1),
+        _ => (false, 0),
+    };
+    let mut matched = false;
+    let mut first = true;
+    while i < class.len() {
+        let start = class[i];
+        if start == ']' && !first {
+            let matched = c.is_some_and(|c| c != '/') && matched != negated;
+            return Some((matched, i + 1));
+        }
+        first = false;
+        if class.get(i + 1) == Some(&'-') && class.get(i + 2).is_some_and(|end| *end != ']') {
+            let end = class[i + 2];
+            matched |= c.is_some_and(|c| start <= c && c <= end);
+            i += 3;
+        } else {
+            matched |= c == Some(start);
+            i += 1;
+        }
+    }
+    None
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedFile {
+    name: String,
+    modified: SystemTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexedDir {
+    modified: Option<SystemTime>, // None forces a re-read on the next refresh
+    #[serde(default)]
+    ignore_modified: Vec<Option<SystemTime>>, // Of .gitignore and .hidden, which can change in place
+    depth: usize,
+    ignore: Vec<IgnoreRule>, // From this directory's .gitignore and .hidden
+    files: Vec<IndexedFile>,
+    subdirs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileIndex {
+    options: IndexOptions,
+    dirs: HashMap<PathBuf, IndexedDir>,
+}
+
+impl FileIndex {
+    /// ~/.cache/synaptrix/file_index.json (honouring XDG_CACHE_HOME)
+    pub fn default_path() -> PathBuf {
+        xdg::cache_home().join("synaptrix").join("file_index.json")
+    }
+
+    /// Load the index saved at `path`, bring it up to date and save it again if
+    /// anything changed. A missing index, or one built with other options, is rebuilt.
+    pub fn load_or_build(options: IndexOptions, path: &Path) -> Self {
+        let mut index = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<FileIndex>(&content).ok())
+            .filter(|index| index.options == options)
+            .unwrap_or_else(|| Self::empty(options));
+        index.update(path);
+        index
+    }
+
+    /// Refresh the index and save it to `path` if anything changed
+    pub fn update(&mut self, path: &Path) {
+        let rescanned = self.refresh();
+        // println!("DEBUG: File index refreshed, {} directories read", rescanned);
+        if rescanned > 0 {
+            if let Err(e) = self.save(path) {
+                eprintln!("Warning: Could not save file index: {}", e);
+            }
+        }
+    }
+
+    pub fn empty(options: IndexOptions) -> Self {
+        Self {
+            options,
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Re-read every directory that changed since it was indexed and drop the ones
+    /// that are gone. Returns how many directories had to be read.
+    pub fn refresh(&mut self) -> usize {
+        let mut walker = Walker {
+            options: &self.options,
+            old: std::mem::take(&mut self.dirs),
+            new: HashMap::new(),
+            visited: HashSet::new(),
+            rescanned: 0,
+        };
+
+        for root in &self.options.roots {
+            let mut rules: Vec<IgnoreRule> = self
+                .options
+                .exclude
+                .iter()
+                .filter_map(|pattern| IgnoreRule::parse(pattern, root))
+                .collect();
+            walker.visit(root, 0, &mut rules);
+        }
+
+        // Directories that disappeared also count as a change worth saving
+        let rescanned = walker.rescanned + walker.old.len();
+        self.dirs = walker.new;
+        rescanned
+    }
+
+    /// The configured directories and every directory indexed below them
+    pub fn directories(&self) -> Vec<PathBuf> {
+        let mut dirs = self.options.roots.clone();
+        dirs.extend(
+            self.dirs
+                .keys()
+                .filter(|dir| !self.options.roots.contains(dir))
+                .cloned(),
+        );
+        dirs
+    }
+
+    /// Every indexed file with its modification time, newest first
+    pub fn files(&self) -> Vec<(PathBuf, SystemTime)> {
+        let mut files: Vec<(PathBuf, SystemTime)> = self
+            .dirs
+            .iter()
+            .flat_map(|(dir, entry)| {
+                entry
+                    .files
+                    .iter()
+                    .map(move |file| (dir.join(&file.name), file.modified))
+            })
+            .collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        files
+    }
+
+    fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+struct Walker<'a> {
+    options: &'a IndexOptions,
+    old: HashMap<PathBuf, IndexedDir>,
+    new: HashMap<PathBuf, IndexedDir>,
+    visited: HashSet<(u64, u64)>, // (device, inode) of every directory walked so far
+    rescanned: usize,
+}
+
+impl Walker<'_> {
+    fn visit(&mut self, dir: &Path, depth: usize, rules: &mut Vec<IgnoreRule>) {
+        // Follows symlinks, so linked directories are indexed too
+        let Ok(metadata) = fs::metadata(dir) else {
+            return;
+        };
+        if !metadata.is_dir() {
+            return;
+        }
+        // A symlink back up the tree (or a second link to the same place) is walked once
+        if !self.visited.insert((metadata.dev(), metadata.ino())) {
+            // println!("DEBUG: Skipping already indexed directory: {:?}", dir);
+            return;
+        }
+
+        let modified = metadata.modified().ok();
+        let ignore_modified = self.ignore_files_modified(dir);
+        let entry = match self.old.remove(dir) {
+            Some(old)
+                if old.modified.is_some()
+                    && old.modified == modified
+                    && old.ignore_modified == ignore_modified
+                    && old.depth == depth =>
+            {
+                old
+            }
+            _ => {
+                self.rescanned += 1;
+                self.scan(dir, depth, modified, ignore_modified, rules)
+            }
+        };
+
+        let inherited = rules.len();
+        rules.extend(entry.ignore.iter().cloned());
+        let subdirs = entry.subdirs.clone();
+        self.new.insert(dir.to_path_buf(), entry);
+
+        for name in subdirs {
+            self.visit(&dir.join(name), depth + 1, rules);
+        }
+        rules.truncate(inherited);
+    }
+
+    fn scan(
+        &self,
+        dir: &Path,
+        depth: usize,
+        modified: Option<SystemTime>,
+        ignore_modified: Vec<Option<SystemTime>>,
+        rules: &[IgnoreRule],
+    ) -> IndexedDir {
+        let ignore = if self.options.use_ignore_files {
+            read_ignore_files(dir)
+        } else {
+            Vec::new()
+        };
+        let mut files = Vec::new();
+        let mut subdirs = Vec::new();
+
+        match fs::read_dir(dir) {
+            Ok(entries) => {
+                let rules: Vec<IgnoreRule> = rules.iter().chain(&ignore).cloned().collect();
+                for entry in entries.filter_map(|e| e.ok()) {
+                    // The index is saved as JSON, which cannot hold other names
+                    let Ok(name) = entry.file_name().into_string() else {
+                        continue;
+                    };
+                    if !self.options.show_hidden && name.starts_with('.') {
+                        continue;
+                    }
+                    let path = entry.path();
+                    // Broken symlinks have no metadata and are skipped
+                    let Ok(metadata) = fs::metadata(&path) else {
+                        continue;
+                    };
+                    if is_ignored(&path, metadata.is_dir(), &rules) {
+                        // println!("DEBUG: Ignoring {:?}", path);
+                        continue;
+                    }
+
+                    if metadata.is_dir() {
+                        if depth < self.options.max_depth {
+                            subdirs.push(name);
+                        }
+                    } else if metadata.is_file() {
+                        files.push(IndexedFile {
+                            name,
+                            modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+                        });
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Warning: Cannot read directory {}: {}", dir.display(), e);
+            }
+        }
+
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        subdirs.sort();
+        let is_settled = |time: &SystemTime| {
+            SystemTime::now()
+                .duration_since(*time)
+                .is_ok_and(|age| age >= RACY_WINDOW)
+        };
+        let modified = modified
+            .filter(|time| is_settled(time) && ignore_modified.iter().flatten().all(is_settled));
+        IndexedDir {
+            modified,
+            ignore_modified,
+            depth,
+            ignore,
+            files,
+            subdirs,
+        }
+    }
+
+    /// When `dir`/.gitignore and `dir`/.hidden were last changed (None if missing)
+    fn ignore_files_modified(&self, dir: &Path) -> Vec<Option<SystemTime>> {
+        if !self.options.use_ignore_files {
+            return Vec::new();
+        }
+        [".gitignore", ".hidden"]
+            .iter()
+            .map(|name| fs::metadata(dir.join(name)).and_then(|m| m.modified()).ok())
+            .collect()
+    }
+}
+
+/// The rules from `dir`/.gitignore and the names listed in `dir`/.hidden
+fn read_ignore_files(dir: &Path) -> Vec<IgnoreRule> {
+    let mut rules = Vec::new();
+    if let Ok(content) = fs::read_to_string(dir.join(".gitignore")) {
+        rules.extend(
+            content
+                .lines()
+                .filter_map(|line| IgnoreRule::parse(line, dir)),
+        );
+    }
+    if let Ok(content) = fs::read_to_string(dir.join(".hidden")) {
+        rules.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| IgnoreRule::hidden_name(name, dir)),
+        );
+    }
+    rules
+}
+
+/// Set the modification time of `path` for tests; `File::set_modified` is newer
+/// than the supported Rust version
+#[cfg(test)]
+pub(crate) fn set_modified(path: &Path, time: SystemTime) {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap();
+    let times = [
+        libc::timespec {
+            tv_sec: 0,
+            tv_nsec: libc::UTIME_OMIT,
+        },
+        libc::timespec {
+            tv_sec: since_epoch.as_secs() as libc::time_t,
+            tv_nsec: since_epoch.subsec_nanos() as _,
+        },
+    ];
+    let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+    // SAFETY: path is a valid C string and times points to the two timespecs utimensat reads
+    let result = unsafe { libc::utimensat(libc::AT_FDCWD, path.as_ptr(), times.as_ptr(), 0) };
+    assert_eq!(result, 0, "{}", std::io::Error::last_os_error());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn options(root: &Path) -> IndexOptions {
+        IndexOptions {
+            roots: vec![root.to_path_buf()],
+            max_depth: 5,
+            show_hidden: false,
+            use_ignore_files: true,
+            exclude: vec!["*.tmp".to_string(), "build/".to_string()],
+        }
+    }
+
+    fn names(index: &FileIndex, root: &Path) -> Vec<String> {
+        let mut names: Vec<String> = index
+            .files()
+            .into_iter()
+            .map(|(path, _)| path.strip_prefix(root).unwrap().display().to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    /// Backdate every directory below `dir`, so the index trusts their timestamps
+    fn backdate(dir: &Path) {
+        let past = SystemTime::now() - Duration::from_secs(3600);
+        for entry in fs::read_dir(dir).unwrap().filter_map(|e| e.ok()) {
+            if entry.file_type().unwrap().is_dir() {
+                backdate(&entry.path());
+            }
+        }
+        set_modified(dir, past);
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "main.rs"));
+        assert!(!glob_match("*.rs", "src/main.rs"));
+        assert!(glob_match("src/*.rs", "src/main.rs"));
+        assert!(glob_match("**/target", "a/b/target"));
+        assert!(glob_match("**/target", "target"));
+        assert!(glob_match("docs/**", "docs/a/b.md"));
+        assert!(glob_match("a/**/b", "a/x/y/b"));
+        assert!(glob_match("file?.txt", "file1.txt"));
+        assert!(glob_match("[a-c]at", "bat"));
+        assert!(!glob_match("[!a-c]at", "bat"));
+        assert!(glob_match("\\*literal", "*literal"));
+        assert!(!glob_match("\\*literal", "xliteral"));
+        assert!(glob_match("[unclosed", "[unclosed"));
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let base = Path::new("/data");
+        let rules: Vec<IgnoreRule> = ["# comment", "*.log", "!keep.log", "/out/", "cache/"]
+            .iter()
+            .filter_map(|line| IgnoreRule::parse(line, base))
+            .collect();
+        assert_eq!(rules.len(), 4);
+
+        assert!(is_ignored(Path::new("/data/a/debug.log"), false, &rules));
+        assert!(!is_ignored(Path::new("/data/a/keep.log"), false, &rules));
+        assert!(is_ignored(Path::new("/data/out"), true, &rules));
+        assert!(!is_ignored(Path::new("/data/a/out"), true, &rules));
+        assert!(is_ignored(Path::new("/data/a/cache"), true, &rules));
+        assert!(!is_ignored(Path::new("/data/a/cache"), false, &rules));
+        assert!(!is_ignored(
+            Path::new("/elsewhere/debug.log"),
+            false,
+            &rules
+        ));
+    }
+
+    #[test]
+    fn test_walks_subdirectories_with_excludes() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        touch(&root.join("top.txt"));
+        touch(&root.join("a/b/deep.txt"));
+        touch(&root.join("a/scratch.tmp"));
+        touch(&root.join("build/output.bin"));
+        touch(&root.join(".secret/key"));
+        touch(&root.join("repo/src/lib.rs"));
+        touch(&root.join("repo/target/debug/app"));
+        fs::write(root.join("repo/.gitignore"), "target/\n").unwrap();
+        touch(&root.join("photos/private.jpg"));
+        touch(&root.join("photos/public.jpg"));
+        fs::write(root.join("photos/.hidden"), "private.jpg\n").unwrap();
+
+        let mut index = FileIndex::empty(options(root));
+        index.refresh();
+        assert_eq!(
+            names(&index, root),
+            vec![
+                "a/b/deep.txt",
+                "photos/public.jpg",
+                "repo/src/lib.rs",
+                "top.txt"
+            ]
+        );
+
+        let mut shallow = options(root);
+        shallow.max_depth = 0;
+        let mut index = FileIndex::empty(shallow);
+        index.refresh();
+        assert_eq!(names(&index, root), vec!["top.txt"]);
+    }
+
+    #[test]
+    fn test_symlink_loop_is_walked_once() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        touch(&root.join("a/file.txt"));
+        std::os::unix::fs::symlink(root, root.join("a/loop")).unwrap();
+
+        let mut index = FileIndex::empty(options(root));
+        index.refresh();
+        assert_eq!(names(&index, root), vec!["a/file.txt"]);
+    }
+
+    #[test]
+    fn test_saved_index_only_rereads_changed_directories() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("files");
+        let index_path = dir.path().join("cache/file_index.json");
+        touch(&root.join("a/one.txt"));
+        touch(&root.join("b/two.txt"));
+        backdate(&root);
+
+        let index = FileIndex::load_or_build(options(&root), &index_path);
+        assert_eq!(names(&index, &root), vec!["a/one.txt", "b/two.txt"]);
+        assert!(index_path.exists());
+
+        // Nothing changed: the saved index is used as is
+        let mut index = FileIndex::load_or_build(options(&root), &index_path);
+        assert_eq!(index.refresh(), 0);
+
+        touch(&root.join("b/three.txt"));
+        fs::remove_dir_all(root.join("a")).unwrap();
+        let index = FileIndex::load_or_build(options(&root), &index_path);
+        assert_eq!(names(&index, &root), vec!["b/three.txt", "b/two.txt"]);
+
+        // Editing an ignore file in place does not touch the directory's timestamp
+        fs::write(root.join("b/.gitignore"), "three.txt\n").unwrap();
+        backdate(&root);
+        let past = SystemTime::now() - Duration::from_secs(60);
+        set_modified(&root.join("b/.gitignore"), past);
+        let index = FileIndex::load_or_build(options(&root), &index_path);
+        assert_eq!(names(&index, &root), vec!["b/two.txt"]);
+        fs::write(root.join("b/.gitignore"), "two.txt\n").unwrap();
+        set_modified(&root.join("b/.gitignore"), past + Duration::from_secs(1));
+        let index = FileIndex::load_or_build(options(&root), &index_path);
+        assert_eq!(names(&index, &root), vec!["b/three.txt"]);
+        fs::remove_file(root.join("b/.gitignore")).unwrap();
+
+        // Other options start from scratch
+        let mut other = options(&root);
+        other.exclude.push("three.txt".to_string());
+        let index = FileIndex::load_or_build(other, &index_path);
+        assert_eq!(names(&index, &root), vec!["b/two.txt"]);
+    }
+}
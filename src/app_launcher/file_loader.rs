@@ -1,13 +1,22 @@
 // file_loader.rs
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fs;
-use std::path::PathBuf;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::app_info::{AppInfo, ItemType, XbelBookmark};
+use super::file_index::FileIndex;
+use crate::app_info::{AppInfo, ItemType};
+use crate::mime;
 use crate::settings::LauncherSettings;
-use crate::utils::get_file_icon;
+use crate::xbel::parse_xbel;
 
-pub fn load_recent_files(recent_files: &mut Vec<AppInfo>, settings: &LauncherSettings) {
+/// Combine the recently used files from the XBEL file with the newest files in `index`
+pub fn load_recent_files(
+    recent_files: &mut Vec<AppInfo>,
+    index: &FileIndex,
+    settings: &LauncherSettings,
+) {
     if !settings.recent_files.enabled {
         // println!("DEBUG: Recent files disabled in settings");
         return;
@@ -17,199 +26,155 @@ pub fn load_recent_files(recent_files: &mut Vec<AppInfo>, settings: &LauncherSet
     // println!("DEBUG: Max files setting: {}", settings.recent_files.max_files);
     // println!("DEBUG: Directories to scan: {:?}", settings.recent_files.directories);
 
-    // Load from directories (existing functionality)
-    let mut directory_files = Vec::new();
-    for dir_path in &settings.recent_files.directories {
-        let expanded_path = dir_path.replace("~", &std::env::var("HOME").unwrap_or_default());
-        // println!("DEBUG: Scanning directory: {}", expanded_path);
-        
-        match fs::read_dir(&expanded_path) {
-            Ok(entries) => {
-                let mut files: Vec<_> = entries
-                    .filter_map(|e| e.ok())
-                    .filter(|e| {
-                        let is_file = e.file_type().map(|ft| ft.is_file()).unwrap_or(false);
-                        if !is_file {
-                            // println!("DEBUG: Skipping non-file: {:?}", e.path());
-                        }
-                        is_file
-                    })
-                    .filter_map(|e| {
-                        let metadata = e.metadata().ok()?;
-                        let modified = metadata.modified().ok()?;
-                        // println!("DEBUG: Found file: {:?} (modified: {:?})", e.path(), modified);
-                        Some((e.path(), modified))
-                    })
-                    .collect();
-
-                // println!("DEBUG: Found {} files in {}", files.len(), expanded_path);
-                files.sort_by(|a, b| b.1.cmp(&a.1)); // Sort by modification time, newest first
-
-                for (path, _) in files {
-                    if let Some(name) = path.file_name() {
-                        let app_info = AppInfo {
-                            name: format!("📄 {}", name.to_string_lossy()),
-                            description: format!("Recent file: {}", path.display()),
-                            exec: String::new(), // Will be handled specially
-                            icon: get_file_icon(&path),
-                            categories: vec!["Recent".to_string()],
-                            item_type: ItemType::RecentFile,
-                            file_path: Some(path.clone()),
-                        };
-                        // println!("DEBUG: Added directory file: {:?}", app_info.name);
-                        directory_files.push(app_info);
-                    }
-                }
-            },
-            Err(e) => {
-                println!("DEBUG: Error reading directory {}: {}", expanded_path, e);
-            }
-        }
-    }
+    // Load from ~/.local/share/recently-used.xbel; these always take priority
+    let mut files = load_from_xbel(settings);
+    let existing_paths: HashSet<PathBuf> = files
+        .iter()
+        .filter_map(|(file, _)| file.file_path.clone())
+        .collect();
+    // println!("DEBUG: Added {} XBEL files", files.len());
 
-    // Load from ~/.local/share/recently-used.xbel
-    let mut xbel_files = Vec::new();
-    load_from_xbel(&mut xbel_files, settings);
-    
-    // Combine files with XBEL files taking priority
-    // First, add all XBEL files
-    let mut existing_paths: HashMap<PathBuf, bool> = HashMap::new();
-    for xbel_file in xbel_files {
-        if let Some(ref path) = xbel_file.file_path {
-            existing_paths.insert(path.clone(), true);
-        }
-        recent_files.push(xbel_file);
-    }
-    
-    // println!("DEBUG: Added {} XBEL files", recent_files.len());
-    
-    // Then add directory files that aren't already in XBEL, up to the limit
-    let remaining_slots = settings.recent_files.max_files.saturating_sub(recent_files.len());
+    // Then add the newest files of the configured directories, up to the limit
+    let remaining_slots = settings.recent_files.max_files.saturating_sub(files.len());
     // println!("DEBUG: Remaining slots for directory files: {}", remaining_slots);
-    
-    let mut added_from_dirs = 0;
-    for dir_file in directory_files {
-        if added_from_dirs >= remaining_slots {
-            break;
-        }
-        
-        if let Some(ref path) = dir_file.file_path {
-            if !existing_paths.contains_key(path) {
-                // println!("DEBUG: Adding directory file: {:?}", dir_file.name);
-                recent_files.push(dir_file);
-                added_from_dirs += 1;
-            } else {
-                // println!("DEBUG: Skipping duplicate directory file: {:?}", path);
-            }
-        }
-    }
-    
-    // println!("DEBUG: Added {} directory files", added_from_dirs);
-    
-    // Final sort by modification time while preserving XBEL priority
-    recent_files.sort_by(|a, b| {
-        let a_time = a
-            .file_path
-            .as_ref()
-            .and_then(|p| fs::metadata(p).ok())
-            .and_then(|m| m.modified().ok());
-        let b_time = b
-            .file_path
-            .as_ref()
-            .and_then(|p| fs::metadata(p).ok())
-            .and_then(|m| m.modified().ok());
-        b_time.cmp(&a_time)
-    });
-    
+    files.extend(
+        index
+            .files()
+            .into_iter()
+            .filter(|(path, _)| !existing_paths.contains(path))
+            .take(remaining_slots)
+            .filter_map(|(path, modified)| Some((file_item(&path)?, modified))),
+    );
+
+    // Newest first: XBEL files by when they were last used, others by modification time
+    files.sort_by_key(|(_, last_used)| std::cmp::Reverse(*last_used));
+    recent_files.extend(files.into_iter().map(|(file, _)| file));
+
     // println!("DEBUG: Total recent files loaded: {}", recent_files.len());
 }
 
-fn load_from_xbel(recent_files: &mut Vec<AppInfo>, settings: &LauncherSettings) {
-
+/// The existing local files in the XBEL file, with the time each was last used
+fn load_from_xbel(settings: &LauncherSettings) -> Vec<(AppInfo, SystemTime)> {
     let xbel_path = &settings.recent_files.xbel_path;
-    
     // println!("DEBUG: Loading from XBEL file: {}", xbel_path);
 
-    match fs::read_to_string(&xbel_path) {
-        Ok(content) => {
-            // println!("DEBUG: XBEL file size: {} bytes", content.len());
-            match parse_xbel(&content) {
-                Ok(xbel_files) => {
-                    // println!("DEBUG: Parsed {} files from XBEL", xbel_files.len());
-                    recent_files.extend(xbel_files);
-                },
-                Err(_e) => {
-                    // println!("DEBUG: Error parsing XBEL: {}", e);
-                }
-            }
-        },
+    let content = match fs::read_to_string(xbel_path) {
+        Ok(content) => content,
+        // No file has been opened through a GTK application yet
+        Err(e) if e.kind() == ErrorKind::NotFound => return Vec::new(),
         Err(e) => {
-            println!("DEBUG: Error reading XBEL file: {}", e);
+            eprintln!("Warning: Could not read {}: {}", xbel_path, e);
+            return Vec::new();
         }
-    }
-}
+    };
+    let bookmarks = match parse_xbel(&content) {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => {
+            eprintln!("Warning: Could not parse {}: {}", xbel_path, e);
+            return Vec::new();
+        }
+    };
 
-fn parse_xbel(content: &str) -> Result<Vec<AppInfo>, Box<dyn std::error::Error>> {
+    let mut seen = HashSet::new();
     let mut files = Vec::new();
-    let mut current_bookmark: Option<XbelBookmark> = None;
-
-    // println!("DEBUG: Parsing XBEL content...");
-    
-    // Simple XML parsing - in production, consider using a proper XML parser like quick-xml
-    for (_line_num, line) in content.lines().enumerate() {
-        let line = line.trim();
-
-        if line.starts_with("<bookmark href=\"file://") {
-            // Extract file path from href
-            if let Some(start) = line.find("file://") {
-                if let Some(end) = line[start..].find("\"") {
-                    let file_url = &line[start..start + end];
-                    let file_path = file_url.replace("file://", "").replace("%20", " ");
-                    // println!("DEBUG: Line {}: Found bookmark for: {}", line_num, file_path);
-
-                    current_bookmark = Some(XbelBookmark {
-                        file_path: PathBuf::from(file_path),
-                        mime_type: String::new(),
-                    });
-                }
-            }
-        } else if line.contains("<mime:mime-type type=\"") {
-            if let Some(bookmark) = &mut current_bookmark {
-                if let Some(start) = line.find("type=\"") {
-                    if let Some(end) = line[start + 6..].find("\"") {
-                        bookmark.mime_type = line[start + 6..start + 6 + end].to_string();
-                        // println!("DEBUG: Line {}: MIME type: {}", line_num, bookmark.mime_type);
-                    }
-                }
-            }
-        } else if line.starts_with("</bookmark>") {
-            if let Some(bookmark) = current_bookmark.take() {
-                // Only include files that actually exist
-                if bookmark.file_path.exists() {
-                    if let Some(name) = bookmark.file_path.file_name() {
-                        let app_info = AppInfo {
-                            name: format!("📄 {}", name.to_string_lossy()),
-                            description: format!(
-                                "Recent file: {}",
-                                bookmark.file_path.display()
-                            ),
-                            exec: String::new(),
-                            icon: get_file_icon(&bookmark.file_path),
-                            categories: vec!["Recent".to_string()],
-                            item_type: ItemType::RecentFile,
-                            file_path: Some(bookmark.file_path.clone()),
-                        };
-                        // println!("DEBUG: Adding XBEL file: {} (exists: {})", 
-                                // app_info.name, bookmark.file_path.exists());
-                        files.push(app_info);
-                    }
-                } else {
-                    // println!("DEBUG: Skipping non-existent file: {:?}", bookmark.file_path);
-                }
-            }
+    // Private bookmarks are only for the applications that registered them
+    for bookmark in bookmarks.into_iter().filter(|bookmark| !bookmark.private) {
+        let Some(path) = bookmark.file_path() else {
+            continue;
+        };
+        // Only include files that actually exist, once each
+        let Ok(metadata) = fs::metadata(&path) else {
+            // println!("DEBUG: Skipping non-existent file: {:?}", path);
+            continue;
+        };
+        if !seen.insert(path.clone()) {
+            continue;
+        }
+
+        let last_used = match bookmark.last_used() {
+            Some(seconds) => UNIX_EPOCH + Duration::from_secs(seconds),
+            None => metadata.modified().unwrap_or(UNIX_EPOCH),
+        };
+        if let Some(file) = file_item(&path) {
+            // println!("DEBUG: Adding XBEL file: {}", file.name);
+            files.push((file, last_used));
         }
     }
+    files
+}
+
+/// The result item for the file at `path`
+pub fn file_item(path: &Path) -> Option<AppInfo> {
+    let name = path.file_name()?;
+    let database = mime::database();
+    let mime_type = database.detect(path);
+    Some(AppInfo {
+        desktop_id: None,
+        name: format!("📄 {}", name.to_string_lossy()),
+        description: format!("Recent file: {}", path.display()),
+        exec: String::new(), // Will be handled specially
+        icon: database.icon_names(&mime_type).into_iter().next(),
+        categories: vec!["Recent".to_string()],
+        keywords: Vec::new(),
+        item_type: ItemType::RecentFile,
+        file_path: Some(path.to_path_buf()),
+        source_path: None,
+        terminal: false,
+        working_dir: None,
+        actions: Vec::new(),
+        mime_types: Vec::new(),
+        mime_type: Some(mime_type),
+        payload: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_launcher::file_index::{set_modified, IndexOptions};
+    use crate::uri::file_uri;
+    use tempfile::tempdir;
 
-    // println!("DEBUG: XBEL parsing complete. Found {} valid files", files.len());
-    Ok(files)
-}
\ No newline at end of file
+    #[test]
+    fn test_xbel_timestamps_order_recent_files() {
+        let dir = tempdir().unwrap();
+        let docs = dir.path().join("docs");
+        fs::create_dir_all(&docs).unwrap();
+        for name in ["old.txt", "used.txt", "plain.txt"] {
+            fs::write(docs.join(name), "").unwrap();
+        }
+        fs::write(dir.path().join("secret.txt"), "").unwrap();
+        // Modified long ago, but opened more recently than everything else
+        set_modified(
+            &docs.join("used.txt"),
+            UNIX_EPOCH + Duration::from_secs(1_000_000_000),
+        );
+
+        let xbel = format!(
+            "<xbel version=\"1.0\">\
+             <bookmark href=\"{}\" modified=\"2001-01-01T00:00:00Z\"/>\
+             <bookmark href=\"{}\" modified=\"2100-01-01T00:00:00Z\"/>\
+             <bookmark href=\"file:///does/not/exist\" modified=\"2100-01-01T00:00:00Z\"/>\
+             <bookmark href=\"{}\" modified=\"2100-01-01T00:00:00Z\">\
+             <info><metadata owner=\"http://freedesktop.org\"><bookmark:private/></metadata></info>\
+             </bookmark>\
+             </xbel>",
+            file_uri(&docs.join("old.txt")),
+            file_uri(&docs.join("used.txt")),
+            file_uri(&dir.path().join("secret.txt")),
+        );
+        let xbel_path = dir.path().join("recently-used.xbel");
+        fs::write(&xbel_path, xbel).unwrap();
+
+        let mut settings = LauncherSettings::default();
+        settings.recent_files.xbel_path = xbel_path.display().to_string();
+        settings.recent_files.directories = vec![docs.display().to_string()];
+        let mut index = FileIndex::empty(IndexOptions::from_settings(&settings.recent_files));
+        index.refresh();
+
+        let mut files = Vec::new();
+        load_recent_files(&mut files, &index, &settings);
+        let names: Vec<&str> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(names, vec!["📄 used.txt", "📄 plain.txt", "📄 old.txt"]);
+    }
+}
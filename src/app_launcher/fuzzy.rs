@@ -0,0 +1,214 @@
+// fuzzy.rs - Fuzzy subsequence matching with ranked scores
+//
+// Every pattern character must appear in the text in order. Among all such
+// alignments the best scoring one is picked: matches at word boundaries and
+// consecutive runs are rewarded, gaps between matched characters cost points.
+// This makes "ff" find Firefox and "vsc" find Visual Studio Code.
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 16;
+const BONUS_BOUNDARY: i64 = 24;
+const BONUS_FIRST_CHAR: i64 = 32;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+// Slightly prefer matches that start early in the text
+const PENALTY_LEADING_CHAR: i64 = 1;
+const MAX_LEADING_PENALTY: i64 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Character (not byte) indices of the matched characters in the text
+    pub positions: Vec<usize>,
+}
+
+/// Lowercase a single character. Lowercasing may change the length for a few
+/// characters ("İ"), so both sides keep only the first one and stay index-aligned.
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Match `pattern` against `text` case-insensitively. Whitespace in the pattern
+/// is ignored. Returns None if the pattern is not a subsequence of the text.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold_case)
+        .collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let original: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = original.iter().copied().map(fold_case).collect();
+    let n = lowered.len();
+    let m = pattern.len();
+    if m > n || !is_subsequence(&pattern, &lowered) {
+        return None;
+    }
+
+    let bonuses: Vec<i64> = (0..n).map(|j| position_bonus(&original, j)).collect();
+
+    // score[i][j]: best score with pattern[..=i] matched and pattern[i] at text[j]
+    // from[i][j]: text index of pattern[i - 1] in that alignment
+    let mut score = vec![vec![None::<i64>; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+
+    for j in 0..n {
+        if lowered[j] == pattern[0] {
+            let leading = (j as i64 * PENALTY_LEADING_CHAR).min(MAX_LEADING_PENALTY);
+            score[0][j] = Some(SCORE_MATCH + bonuses[j] - leading);
+        }
+    }
+
+    for i in 1..m {
+        // Best predecessor ending at least two characters back, already charged
+        // for the gap up to the current column
+        let mut gapped: Option<(i64, usize)> = None;
+
+        for j in i..n {
+            if j >= 2 {
+                if let Some(prev) = score[i - 1][j - 2] {
+                    let candidate = prev - PENALTY_GAP_START;
+                    gapped = match gapped {
+                        Some((best, k)) if best - PENALTY_GAP_EXTENSION >= candidate => {
+                            Some((best - PENALTY_GAP_EXTENSION, k))
+                        }
+                        _ => Some((candidate, j - 2)),
+                    };
+                } else if let Some((best, k)) = gapped {
+                    gapped = Some((best - PENALTY_GAP_EXTENSION, k));
+                }
+            }
+
+            if lowered[j] != pattern[i] {
+                continue;
+            }
+
+            let consecutive = score[i - 1][j - 1].map(|prev| (prev + BONUS_CONSECUTIVE, j - 1));
+            let best = match (consecutive, gapped) {
+                (Some(c), Some(g)) => Some(if c.0 >= g.0 { c } else { g }),
+                (c, g) => c.or(g),
+            };
+
+            if let Some((prev_score, k)) = best {
+                score[i][j] = Some(prev_score + SCORE_MATCH + bonuses[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, best) = score[m - 1]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, s)| s.map(|s| (j, s)))
+        .max_by_key(|&(j, s)| (s, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    Some(FuzzyMatch {
+        score: best,
+        positions,
+    })
+}
+
+fn is_subsequence(pattern: &[char], text: &[char]) -> bool {
+    let mut remaining = pattern.iter().peekable();
+    for c in text {
+        if remaining.peek() == Some(&c) {
+            remaining.next();
+        }
+    }
+    remaining.peek().is_none()
+}
+
+/// Bonus for matching the character at `j`: start of text, start of a word,
+/// or an uppercase letter following a lowercase one (camelCase)
+fn position_bonus(text: &[char], j: usize) -> i64 {
+    if j == 0 {
+        return BONUS_FIRST_CHAR;
+    }
+    let prev = text[j - 1];
+    let current = text[j];
+    let word_start = !prev.is_alphanumeric() && current.is_alphanumeric();
+    let camel_case = prev.is_lowercase() && current.is_uppercase();
+    if word_start || camel_case {
+        BONUS_BOUNDARY
+    } else if prev.is_alphabetic() && current.is_numeric() {
+        BONUS_BOUNDARY / 2
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> i64 {
+        fuzzy_match(pattern, text).map(|m| m.score).unwrap_or(i64::MIN)
+    }
+
+    #[test]
+    fn test_subsequence_required() {
+        assert!(fuzzy_match("ff", "Firefox Web Browser").is_some());
+        assert!(fuzzy_match("fx", "Firefox").is_some());
+        assert!(fuzzy_match("xf", "Firefox").is_none());
+        assert!(fuzzy_match("firefoxes", "Firefox").is_none());
+        assert_eq!(fuzzy_match("", "Anything").unwrap().score, 0);
+    }
+
+    #[test]
+    fn test_case_insensitive_and_positions() {
+        let m = fuzzy_match("FiFo", "Firefox").unwrap();
+        assert_eq!(m.positions, vec![0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn test_acronyms_use_word_boundaries() {
+        let m = fuzzy_match("vsc", "Visual Studio Code").unwrap();
+        assert_eq!(m.positions, vec![0, 7, 14]);
+        assert!(score("vsc", "Visual Studio Code") > score("vsc", "Avs Convertor"));
+        assert_eq!(fuzzy_match("gc", "GnomeCalculator").unwrap().positions, vec![0, 5]);
+    }
+
+    #[test]
+    fn test_prefix_and_word_matches_rank_first() {
+        // "term" should prefer a word start over a match inside a word
+        assert!(score("term", "Terminal") > score("term", "Determine"));
+        assert!(score("term", "Alacritty Terminal") > score("term", "Xterm-like Viewer"));
+        // A prefix beats the same word later in the name
+        assert!(score("term", "Terminal") > score("term", "Alacritty Terminal"));
+    }
+
+    #[test]
+    fn test_consecutive_beats_scattered() {
+        assert!(score("code", "Code Editor") > score("code", "Color Dedicated"));
+        let m = fuzzy_match("box", "Boxes Box").unwrap();
+        assert_eq!(m.positions, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_whitespace_in_pattern_is_ignored() {
+        let m = fuzzy_match("vs code", "Visual Studio Code").unwrap();
+        assert_eq!(m.positions, vec![0, 7, 14, 15, 16, 17]);
+    }
+
+    #[test]
+    fn test_unicode_positions_are_char_indices() {
+        let m = fuzzy_match("éd", "📄 Éditeur.txt").unwrap();
+        assert_eq!(m.positions, vec![2, 3]);
+
+        // "İ" lowercases to two chars; pattern and text must fold it the same way
+        let m = fuzzy_match("İs", "İstanbul").unwrap();
+        assert_eq!(m.positions, vec![0, 1]);
+    }
+}
@@ -0,0 +1,267 @@
+// mime_apps.rs - Applications associated with MIME types
+//
+// Implements the lookup of the MIME Applications Associations spec: the
+// mimeapps.list files (optionally prefixed with the desktop name) in the config
+// and data directories, combined with the MimeType= keys of installed applications.
+// See https://specifications.freedesktop.org/mime-apps-spec/latest/
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::desktop_entry::split_list;
+use crate::app_info::AppInfo;
+
+const DEFAULT_APPLICATIONS: &str = "Default Applications";
+const ADDED_ASSOCIATIONS: &str = "Added Associations";
+const REMOVED_ASSOCIATIONS: &str = "Removed Associations";
+
+/// The associations of every mimeapps.list, most important first
+#[derive(Debug, Clone, Default)]
+pub struct MimeApps {
+    lists: Vec<MimeAppsList>,
+}
+
+/// One mimeapps.list file; every group maps MIME types to desktop-file IDs
+#[derive(Debug, Clone, Default)]
+struct MimeAppsList {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, Vec<String>>,
+}
+
+/// The mimeapps.list files in order of importance: for each config directory and
+/// then each applications/ data directory, the desktop-specific files before the plain one
+pub fn mimeapps_paths(
+    config_dirs: &[PathBuf],
+    data_dirs: &[PathBuf],
+    desktops: &[String],
+) -> Vec<PathBuf> {
+    let dirs = config_dirs
+        .iter()
+        .cloned()
+        .chain(data_dirs.iter().map(|dir| dir.join("applications")));
+
+    let mut paths = Vec::new();
+    for dir in dirs {
+        for desktop in desktops {
+            paths.push(dir.join(format!("{}-mimeapps.list", desktop.to_lowercase())));
+        }
+        paths.push(dir.join("mimeapps.list"));
+    }
+    paths
+}
+
+impl MimeApps {
+    /// Read the files at `paths`, most important first. Missing files are skipped.
+    pub fn load(paths: &[PathBuf]) -> Self {
+        let lists = paths
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .map(|content| MimeAppsList::parse(&content))
+            .collect();
+        Self { lists }
+    }
+
+    /// Desktop-file IDs of the installed applications for `mime_type`, in order of
+    /// preference: the default application, the added associations, and then the
+    /// applications declaring the type in MimeType=, by name.
+    pub fn handlers(&self, mime_type: &str, installed: &HashMap<String, AppInfo>) -> Vec<String> {
+        let mime_type = mime_type.to_lowercase();
+        let mut handlers: Vec<String> = Vec::new();
+        let push = |handlers: &mut Vec<String>, id: &str| {
+            if installed.contains_key(id) && !handlers.iter().any(|h| h == id) {
+                handlers.push(id.to_string());
+            }
+        };
+
+        // The first installed default in the most important file that has one
+        if let Some(default) = self
+            .lists
+            .iter()
+            .filter_map(|list| list.defaults.get(&mime_type))
+            .flatten()
+            .find(|id| installed.contains_key(*id))
+        {
+            push(&mut handlers, default);
+        }
+
+        // A removal only hides associations made by less important files
+        let mut removed: HashSet<&str> = HashSet::new();
+        for list in &self.lists {
+            for id in list.added.get(&mime_type).into_iter().flatten() {
+                if !removed.contains(id.as_str()) {
+                    push(&mut handlers, id);
+                }
+            }
+            removed.extend(
+                list.removed
+                    .get(&mime_type)
+                    .into_iter()
+                    .flatten()
+                    .map(String::as_str),
+            );
+        }
+
+        let mut declared: Vec<(&String, &AppInfo)> = installed
+            .iter()
+            .filter(|(id, _)| !removed.contains(id.as_str()))
+            .filter(|(_, app)| {
+                app.mime_types
+                    .iter()
+                    .any(|declared| declared.eq_ignore_ascii_case(&mime_type))
+            })
+            .collect();
+        declared.sort_by(|(a_id, a), (b_id, b)| a.name.cmp(&b.name).then_with(|| a_id.cmp(b_id)));
+        for (id, _) in declared {
+            push(&mut handlers, id);
+        }
+
+        handlers
+    }
+}
+
+impl MimeAppsList {
+    /// Parse the key file; keys are MIME types, so they are not checked like desktop entry keys
+    fn parse(content: &str) -> Self {
+        let mut list = Self::default();
+        let mut current: Option<&mut HashMap<String, Vec<String>>> = None;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                current = match &line[1..line.len() - 1] {
+                    DEFAULT_APPLICATIONS => Some(&mut list.defaults),
+                    ADDED_ASSOCIATIONS => Some(&mut list.added),
+                    REMOVED_ASSOCIATIONS => Some(&mut list.removed),
+                    _ => None,
+                };
+                continue;
+            }
+
+            let (Some(group), Some((key, value))) = (current.as_mut(), line.split_once('=')) else {
+                continue;
+            };
+            group
+                .entry(key.trim().to_lowercase())
+                .or_insert_with(|| split_list(value.trim()));
+        }
+        list
+    }
+}
+
+/// Whether `path` is one of the mimeapps.list files, e.g. to react to changes
+pub fn is_mimeapps_list(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with("mimeapps.list"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_info::ItemType;
+    use tempfile::tempdir;
+
+    fn app(name: &str, mime_types: &[&str]) -> AppInfo {
+        AppInfo {
+            desktop_id: None,
+            name: name.to_string(),
+            description: String::new(),
+            exec: name.to_lowercase(),
+            icon: None,
+            categories: Vec::new(),
+            keywords: Vec::new(),
+            item_type: ItemType::Application,
+            file_path: None,
+            source_path: None,
+            terminal: false,
+            working_dir: None,
+            actions: Vec::new(),
+            mime_types: mime_types.iter().map(|m| m.to_string()).collect(),
+            mime_type: None,
+            payload: None,
+        }
+    }
+
+    fn installed() -> HashMap<String, AppInfo> {
+        [
+            ("gedit.desktop", app("Text Editor", &["text/plain"])),
+            ("kate.desktop", app("Kate", &["text/plain", "text/x-csrc"])),
+            ("vim.desktop", app("Vim", &["text/plain"])),
+            ("code.desktop", app("Code", &[])),
+        ]
+        .into_iter()
+        .map(|(id, app)| (id.to_string(), app))
+        .collect()
+    }
+
+    #[test]
+    fn test_paths_follow_spec_order() {
+        let paths = mimeapps_paths(
+            &[PathBuf::from("/home/u/.config"), PathBuf::from("/etc/xdg")],
+            &[PathBuf::from("/usr/share")],
+            &["GNOME".to_string()],
+        );
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/home/u/.config/gnome-mimeapps.list"),
+                PathBuf::from("/home/u/.config/mimeapps.list"),
+                PathBuf::from("/etc/xdg/gnome-mimeapps.list"),
+                PathBuf::from("/etc/xdg/mimeapps.list"),
+                PathBuf::from("/usr/share/applications/gnome-mimeapps.list"),
+                PathBuf::from("/usr/share/applications/mimeapps.list"),
+            ]
+        );
+        assert!(is_mimeapps_list(&paths[0]));
+        assert!(!is_mimeapps_list(Path::new(
+            "/usr/share/applications/vim.desktop"
+        )));
+    }
+
+    #[test]
+    fn test_handlers_combine_lists_and_declared_types() {
+        let dir = tempdir().unwrap();
+        let user = dir.path().join("user.list");
+        let system = dir.path().join("system.list");
+        fs::write(
+            &user,
+            "[Default Applications]\ntext/plain=missing.desktop;kate.desktop;\n\
+             [Added Associations]\nText/Plain=code.desktop;\n\
+             [Removed Associations]\ntext/plain=vim.desktop;\n",
+        )
+        .unwrap();
+        fs::write(
+            &system,
+            "[Default Applications]\ntext/plain=gedit.desktop\n\
+             [Added Associations]\ntext/plain=vim.desktop;gedit.desktop;\n\
+             [Removed Associations]\ntext/plain=code.desktop;\n",
+        )
+        .unwrap();
+
+        let mime_apps = MimeApps::load(&[user, dir.path().join("missing.list"), system]);
+        // The user's default wins; the user's removal hides vim, the system's removal
+        // cannot hide what the user added
+        assert_eq!(
+            mime_apps.handlers("text/plain", &installed()),
+            vec!["kate.desktop", "code.desktop", "gedit.desktop"]
+        );
+        assert_eq!(
+            mime_apps.handlers("text/x-csrc", &installed()),
+            vec!["kate.desktop"]
+        );
+        assert!(mime_apps.handlers("image/png", &installed()).is_empty());
+    }
+
+    #[test]
+    fn test_declared_types_without_lists() {
+        let mime_apps = MimeApps::default();
+        assert_eq!(
+            mime_apps.handlers("text/plain", &installed()),
+            vec!["kate.desktop", "gedit.desktop", "vim.desktop"]
+        );
+    }
+}
@@ -2,8 +2,16 @@ pub mod app_launcher;
 pub mod ui;
 pub mod keyboard;
 pub mod search;
+pub mod result_list;
+pub mod search_worker;
 pub mod file_loader;
+pub mod file_index;
 pub mod desktop_parser;
+pub mod desktop_entry;
+pub mod mime_apps;
+pub mod fuzzy;
+pub mod providers;
 pub mod styles;
+pub mod watcher;
 
 pub use app_launcher::AppLauncher;
\ No newline at end of file
@@ -0,0 +1,273 @@
+// applications.rs - Installed desktop applications
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use super::{score_item, SearchProvider, SearchResult};
+use crate::app_info::AppInfo;
+use crate::app_launcher::desktop_parser;
+use crate::app_launcher::mime_apps::{self, MimeApps};
+use crate::settings::LauncherSettings;
+use crate::utils::launch_application;
+use crate::xdg;
+
+pub const ID: &str = "applications";
+
+pub struct ApplicationsProvider {
+    apps: RwLock<HashMap<String, AppInfo>>, // keyed by desktop-file ID
+    data_dirs: Vec<PathBuf>,                // Searched for applications/, by precedence
+    desktops: Option<Vec<String>>,          // OnlyShowIn/NotShowIn filter
+    mime_apps: RwLock<MimeApps>,
+    mimeapps_paths: Vec<PathBuf>, // By importance
+}
+
+impl ApplicationsProvider {
+    pub fn load(settings: &LauncherSettings) -> Self {
+        let data_dirs = xdg::data_search_path();
+        let mut config_dirs = vec![xdg::config_home()];
+        config_dirs.extend(xdg::config_dirs());
+        let mimeapps_paths =
+            mime_apps::mimeapps_paths(&config_dirs, &data_dirs, &xdg::current_desktops());
+        Self::from_dirs(
+            data_dirs,
+            mimeapps_paths,
+            desktop_parser::desktop_filter(settings),
+        )
+    }
+
+    pub fn from_dirs(
+        data_dirs: Vec<PathBuf>,
+        mimeapps_paths: Vec<PathBuf>,
+        desktops: Option<Vec<String>>,
+    ) -> Self {
+        let mut apps = HashMap::new();
+        desktop_parser::load_applications_from(&mut apps, &data_dirs, desktops.as_deref());
+        Self {
+            apps: RwLock::new(apps),
+            data_dirs,
+            desktops,
+            mime_apps: RwLock::new(MimeApps::load(&mimeapps_paths)),
+            mimeapps_paths,
+        }
+    }
+
+    pub fn new(apps: HashMap<String, AppInfo>) -> Self {
+        Self {
+            apps: RwLock::new(apps),
+            data_dirs: Vec::new(),
+            desktops: None,
+            mime_apps: RwLock::new(MimeApps::default()),
+            mimeapps_paths: Vec::new(),
+        }
+    }
+
+    /// The applications that can open files of `mime_types`, the preferred one first.
+    /// Handlers of the first type come before those of the later, more generic ones.
+    pub fn apps_for_mime_types(&self, mime_types: &[String]) -> Vec<AppInfo> {
+        let (Ok(apps), Ok(mime_apps)) = (self.apps.read(), self.mime_apps.read()) else {
+            return Vec::new();
+        };
+        let mut ids: Vec<String> = Vec::new();
+        for id in mime_types
+            .iter()
+            .flat_map(|mime_type| mime_apps.handlers(mime_type, &apps))
+        {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids.iter().filter_map(|id| apps.get(id).cloned()).collect()
+    }
+}
+
+impl SearchProvider for ApplicationsProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let Ok(apps) = self.apps.read() else {
+            return Vec::new();
+        };
+        let mut results: Vec<SearchResult> = apps
+            .values()
+            .filter_map(|app| score_item(app, query))
+            .collect();
+        // println!("DEBUG: Matched {} of {} apps", results.len(), apps.len());
+
+        // Best matches first; ties (and the empty query) fall back to name order, keeping
+        // apps that share a name in a stable order
+        results.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| a.item.name.cmp(&b.item.name))
+                .then_with(|| a.item.desktop_id.cmp(&b.item.desktop_id))
+        });
+        results
+    }
+
+    fn activate(&self, item: &AppInfo, settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        // println!("DEBUG: Launching application: {}", item.exec);
+        launch_application(item, settings)
+    }
+
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        let mut paths = desktop_parser::applications_dirs(&self.data_dirs);
+        // The lists in applications/ are covered by watching the directory
+        let lists: Vec<PathBuf> = self
+            .mimeapps_paths
+            .iter()
+            .filter(|path| {
+                !path
+                    .parent()
+                    .is_some_and(|dir| paths.iter().any(|p| p == dir))
+            })
+            .cloned()
+            .collect();
+        paths.extend(lists);
+        paths
+    }
+
+    fn refresh(&self, changed: &[PathBuf]) {
+        if changed.iter().any(|path| mime_apps::is_mimeapps_list(path)) {
+            // println!("DEBUG: Reloading MIME associations");
+            if let Ok(mut mime_apps) = self.mime_apps.write() {
+                *mime_apps = MimeApps::load(&self.mimeapps_paths);
+            }
+        }
+
+        // Listing the directories again is cheap; only desktop files that changed,
+        // appeared or now take precedence over another one are parsed again
+        let mut apps = HashMap::new();
+        {
+            let Ok(current) = self.apps.read() else {
+                return;
+            };
+            for (id, path) in desktop_parser::resolve_desktop_files(&self.data_dirs) {
+                let unchanged = current
+                    .get(&id)
+                    .filter(|app| app.source_path.as_ref() == Some(&path))
+                    .filter(|_| !changed.contains(&path));
+                if let Some(app) = unchanged {
+                    apps.insert(id, app.clone());
+                } else if let Ok(mut app) =
+                    desktop_parser::parse_desktop_file(&path, self.desktops.as_deref())
+                {
+                    // println!("DEBUG: Reloaded desktop file: {:?}", path);
+                    app.desktop_id = Some(id.clone());
+                    apps.insert(id, app);
+                }
+            }
+        }
+
+        if let Ok(mut current) = self.apps.write() {
+            *current = apps;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn write_entry(path: &PathBuf, name: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            path,
+            format!(
+                "[Desktop Entry]\nType=Application\nName={}\nExec=true\n",
+                name
+            ),
+        )
+        .unwrap();
+    }
+
+    fn names(provider: &ApplicationsProvider) -> Vec<String> {
+        provider
+            .search("")
+            .into_iter()
+            .map(|result| result.item.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_refresh_picks_up_changed_desktop_files() {
+        let user = tempdir().unwrap();
+        let system = tempdir().unwrap();
+        let editor = system.path().join("applications/editor.desktop");
+        write_entry(&editor, "Editor");
+
+        let provider = ApplicationsProvider::from_dirs(
+            vec![user.path().to_path_buf(), system.path().to_path_buf()],
+            Vec::new(),
+            None,
+        );
+        assert_eq!(names(&provider), vec!["Editor"]);
+        assert!(provider
+            .watched_paths()
+            .contains(&user.path().join("applications")));
+
+        // A new application and an edited one
+        let viewer = system.path().join("applications/viewer.desktop");
+        write_entry(&viewer, "Viewer");
+        write_entry(&editor, "Text Editor");
+        provider.refresh(&[viewer, editor.clone()]);
+        assert_eq!(names(&provider), vec!["Text Editor", "Viewer"]);
+
+        // A user entry shadows the system one without the system file changing
+        let override_entry = user.path().join("applications/editor.desktop");
+        write_entry(&override_entry, "My Editor");
+        provider.refresh(std::slice::from_ref(&override_entry));
+        assert_eq!(names(&provider), vec!["My Editor", "Viewer"]);
+
+        fs::remove_file(&override_entry).unwrap();
+        provider.refresh(&[override_entry]);
+        assert_eq!(names(&provider), vec!["Text Editor", "Viewer"]);
+    }
+
+    #[test]
+    fn test_apps_for_mime_types_follow_mimeapps_list() {
+        let data = tempdir().unwrap();
+        let config = tempdir().unwrap();
+        for (id, name) in [("viewer", "Viewer"), ("editor", "Editor")] {
+            let path = data.path().join(format!("applications/{}.desktop", id));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(
+                &path,
+                format!(
+                    "[Desktop Entry]\nType=Application\nName={}\nExec={} %f\nMimeType=text/plain;\n",
+                    name, id
+                ),
+            )
+            .unwrap();
+        }
+        let list = config.path().join("mimeapps.list");
+        let provider = ApplicationsProvider::from_dirs(
+            vec![data.path().to_path_buf()],
+            vec![list.clone()],
+            None,
+        );
+        let names = |mime_type: &str| -> Vec<String> {
+            provider
+                .apps_for_mime_types(&[mime_type.to_string()])
+                .into_iter()
+                .map(|app| app.name)
+                .collect()
+        };
+        assert_eq!(names("text/plain"), vec!["Editor", "Viewer"]);
+        assert!(provider.watched_paths().contains(&list));
+
+        fs::write(&list, "[Default Applications]\ntext/plain=viewer.desktop\n").unwrap();
+        provider.refresh(std::slice::from_ref(&list));
+        assert_eq!(names("text/plain"), vec!["Viewer", "Editor"]);
+        assert!(names("image/png").is_empty());
+
+        // Applications for a more generic type are offered for its subtypes
+        let generic =
+            provider.apps_for_mime_types(&["text/x-rust".to_string(), "text/plain".to_string()]);
+        assert_eq!(generic.len(), 2);
+    }
+}
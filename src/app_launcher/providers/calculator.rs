@@ -0,0 +1,180 @@
+// calculator.rs - Evaluate arithmetic typed into the search field
+use std::error::Error;
+
+use super::{SearchProvider, SearchResult};
+use crate::app_info::{AppInfo, ItemType};
+use crate::expression::{evaluate, format_number};
+use crate::settings::LauncherSettings;
+use crate::utils::copy_text;
+
+pub const ID: &str = "calculator";
+
+// Values this large lose whole-number precision as f64, so they get no hex form
+const MAX_EXACT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+pub struct CalculatorProvider;
+
+const OPERATORS: [char; 10] = ['+', '-', '*', '/', '^', '%', '!', '(', '×', '÷'];
+// On their own these also separate the parts of dates and phone numbers
+const SEPARATORS: [char; 3] = ['-', '/', '('];
+
+/// The expression in `query`: everything after a leading "=", or the query itself
+/// if it looks like arithmetic rather than a name, date or phone number. That takes
+/// an operator that never separates dates or phone numbers ("2*3", "5!"), an
+/// operator between spaces ("1 - 2"), a function or constant ("sqrt(2)"), two
+/// different operators ("(3+4)/2") or a literal ("0xff"), but not "e", "2048",
+/// "2024-10-17" or "555-1234".
+fn expression(query: &str) -> Option<&str> {
+    if let Some(expression) = query.strip_prefix('=') {
+        return Some(expression);
+    }
+    if !query.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let is_literal = ["0x", "0b", "0o"]
+        .iter()
+        .any(|prefix| query.starts_with(prefix));
+
+    let chars: Vec<char> = query.chars().collect();
+    // A leading sign is not an operator: "+1-555-1234" is a phone number
+    let unsigned = query.strip_prefix(['+', '-']).unwrap_or(query);
+    let mut operators: Vec<char> = unsigned.chars().filter(|c| OPERATORS.contains(c)).collect();
+    operators.sort_unstable();
+    operators.dedup();
+    let spaced = chars.windows(3).any(|window| {
+        window[0].is_whitespace() && OPERATORS.contains(&window[1]) && window[2].is_whitespace()
+    });
+    // Unknown names fail to evaluate, so any name will do here
+    let named = chars.iter().any(|c| c.is_alphabetic());
+
+    let unambiguous = operators.iter().any(|c| !SEPARATORS.contains(c));
+
+    let arithmetic =
+        !operators.is_empty() && (unambiguous || spaced || named || operators.len() > 1);
+    (arithmetic || is_literal).then_some(query)
+}
+
+fn result_item(expression: &str, value: f64) -> AppInfo {
+    let result = format_number(value);
+    let mut description = format!("{} · Enter copies the result", expression.trim());
+    if value.fract() == 0.0 && value.abs() < MAX_EXACT_INTEGER {
+        let sign = if value < 0.0 { "-" } else { "" };
+        description.push_str(&format!(" · {}0x{:x}", sign, value.abs() as u64));
+    }
+    copy_item(format!("= {}", result), description, result)
+}
+
+/// A result row showing `name` that copies `value` when activated
+pub fn copy_item(name: String, description: String, value: String) -> AppInfo {
+    AppInfo {
+        desktop_id: None,
+        name,
+        description,
+        exec: String::new(),
+        icon: Some("accessories-calculator".to_string()),
+        categories: vec!["Calculator".to_string()],
+        keywords: Vec::new(),
+        item_type: ItemType::Calculator,
+        file_path: None,
+        source_path: None,
+        terminal: false,
+        working_dir: None,
+        actions: Vec::new(),
+        mime_types: Vec::new(),
+        mime_type: None,
+        payload: Some(value),
+    }
+}
+
+/// Copy the value of an item made by `copy_item`
+pub fn copy_result(item: &AppInfo) -> Result<(), Box<dyn Error>> {
+    let result = item.payload.as_deref().ok_or("No result to copy")?;
+    copy_text(result)
+}
+
+impl SearchProvider for CalculatorProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let Some(expression) = expression(query.trim()) else {
+            return Vec::new();
+        };
+        let Ok(value) = evaluate(expression) else {
+            return Vec::new();
+        };
+        vec![SearchResult {
+            item: result_item(expression, value),
+            score: 0,
+            highlights: Vec::new(),
+        }]
+    }
+
+    fn activate(&self, item: &AppInfo, _settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        copy_result(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(query: &str) -> Option<String> {
+        let results = CalculatorProvider.search(query);
+        results
+            .first()
+            .and_then(|result| result.item.payload.clone())
+    }
+
+    #[test]
+    fn test_arithmetic_queries() {
+        assert_eq!(result("2*(3+4)").as_deref(), Some("14"));
+        assert_eq!(result("2*3").as_deref(), Some("6"));
+        assert_eq!(result("2^10").as_deref(), Some("1024"));
+        assert_eq!(result("10÷4").as_deref(), Some("2.5"));
+        assert_eq!(result("5!").as_deref(), Some("120"));
+        assert_eq!(result("=2 * (3 + 4)").as_deref(), Some("14"));
+        assert_eq!(result("sqrt(2)/3").as_deref(), Some("0.471404520791"));
+        assert_eq!(result("0xff").as_deref(), Some("255"));
+        assert_eq!(result("=pi").as_deref(), Some("3.14159265359"));
+        assert_eq!(result("10 - 17").as_deref(), Some("-7"));
+        assert_eq!(result("200 + 10%").as_deref(), Some("220"));
+        assert_eq!(result("=10/4").as_deref(), Some("2.5"));
+    }
+
+    #[test]
+    fn test_names_and_commands_are_left_alone() {
+        for query in [
+            "",
+            "e",
+            "pi",
+            "2048",
+            "firefox",
+            "7-zip",
+            "ls -la",
+            "/usr/bin/ls",
+            "=2 +",
+            "2024-10-17",
+            "10/17",
+            "10/17/2024",
+            "555-1234",
+            "+1-555-1234",
+        ] {
+            assert!(
+                result(query).is_none(),
+                "{} should not be calculated",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn test_result_item() {
+        let item = &CalculatorProvider.search("=-255")[0].item;
+        assert_eq!(item.name, "= -255");
+        assert_eq!(item.description, "-255 · Enter copies the result · -0xff");
+        assert_eq!(item.item_type, ItemType::Calculator);
+        assert_eq!(item.history_id(), None);
+    }
+}
@@ -0,0 +1,103 @@
+// commands.rs - Run the query as a shell command
+use std::error::Error;
+
+use super::{SearchProvider, SearchResult};
+use crate::app_info::{AppInfo, ItemType};
+use crate::exec::quote_arg;
+use crate::settings::LauncherSettings;
+use crate::utils::launch_application;
+
+pub const ID: &str = "commands";
+
+pub struct CommandProvider;
+
+/// Check if it's a command (starts with common command prefixes or contains /)
+fn is_command(query: &str) -> bool {
+    query.starts_with('/')
+        || query.starts_with("./")
+        || query.contains(' ')
+        || [
+            "sudo", "cd", "ls", "cat", "grep", "find", "ps", "kill", "git",
+        ]
+        .iter()
+        .any(|&cmd| query.starts_with(cmd))
+}
+
+fn command_item(query: &str) -> AppInfo {
+    let execq = if let Some(rest) = query.strip_prefix('/') {
+        rest
+    } else if let Some(rest) = query.strip_prefix("./") {
+        rest
+    } else {
+        query
+    };
+
+    AppInfo {
+        desktop_id: None,
+        name: format!("💻 Run: {}", execq),
+        description: "Execute command in terminal".to_string(),
+        exec: if query.starts_with("sudo") {
+            format!("pkexec sh -c {}", quote_arg(execq))
+        } else {
+            format!("sh -c {}", quote_arg(execq))
+        },
+        icon: Some("utilities-terminal".to_string()),
+        categories: vec!["Command".to_string()],
+        keywords: Vec::new(),
+        item_type: ItemType::Command,
+        file_path: None,
+        source_path: None,
+        terminal: false,
+        working_dir: None,
+        actions: Vec::new(),
+        mime_types: Vec::new(),
+        mime_type: None,
+        payload: None,
+    }
+}
+
+impl SearchProvider for CommandProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        if query.is_empty() || !is_command(query) {
+            return Vec::new();
+        }
+        // println!("DEBUG: Added command item for: {}", query);
+        vec![SearchResult {
+            item: command_item(query),
+            score: 0,
+            highlights: Vec::new(),
+        }]
+    }
+
+    fn activate(&self, item: &AppInfo, settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        launch_application(item, settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_command_like_queries_match() {
+        assert!(CommandProvider.search("").is_empty());
+        assert!(CommandProvider.search("firefox").is_empty());
+        assert_eq!(CommandProvider.search("ls -la").len(), 1);
+        assert_eq!(CommandProvider.search("git").len(), 1);
+    }
+
+    #[test]
+    fn test_command_item_exec() {
+        let item = &CommandProvider.search("/echo 'hi'")[0].item;
+        assert_eq!(item.name, "💻 Run: echo 'hi'");
+        assert_eq!(item.exec, "sh -c \"echo 'hi'\"");
+        assert_eq!(item.item_type, ItemType::Command);
+
+        let item = &CommandProvider.search("sudo apt update")[0].item;
+        assert_eq!(item.exec, "pkexec sh -c \"sudo apt update\"");
+    }
+}
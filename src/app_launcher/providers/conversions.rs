@@ -0,0 +1,73 @@
+// conversions.rs - Convert units typed as "5 mi to km" or "72f in c"
+use std::error::Error;
+
+use super::calculator::{copy_item, copy_result};
+use super::{SearchProvider, SearchResult};
+use crate::app_info::AppInfo;
+use crate::expression::format_number;
+use crate::settings::LauncherSettings;
+use crate::units::convert;
+
+pub const ID: &str = "conversions";
+
+pub struct ConversionProvider;
+
+impl SearchProvider for ConversionProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        // Queries that are not conversions, or convert between different kinds of units
+        let Some(Ok(conversion)) = convert(query.trim()) else {
+            return Vec::new();
+        };
+
+        let value = format_number(conversion.value);
+        let unit = conversion.unit.symbol();
+        let item = copy_item(
+            format!("= {} {}", value, unit),
+            format!(
+                "{} in {} · Enter copies the result",
+                conversion.quantity, unit
+            ),
+            value,
+        );
+        vec![SearchResult {
+            item,
+            score: 0,
+            highlights: Vec::new(),
+        }]
+    }
+
+    fn activate(&self, item: &AppInfo, _settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        copy_result(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_info::ItemType;
+
+    #[test]
+    fn test_conversion_results() {
+        let results = ConversionProvider.search("5 mi to km");
+        assert_eq!(results.len(), 1);
+        let item = &results[0].item;
+        assert_eq!(item.name, "= 8.04672 km");
+        assert_eq!(item.description, "5 mi in km · Enter copies the result");
+        assert_eq!(item.payload.as_deref(), Some("8.04672"));
+        assert_eq!(item.item_type, ItemType::Calculator);
+
+        let item = &ConversionProvider.search("2gib in mb")[0].item;
+        assert_eq!(item.name, "= 2147.483648 MB");
+    }
+
+    #[test]
+    fn test_other_queries_have_no_results() {
+        for query in ["", "firefox", "5 kg to km", "ls -la", "install to home"] {
+            assert!(ConversionProvider.search(query).is_empty(), "{}", query);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+// providers/mod.rs - Pluggable sources of search results
+//
+// Each provider turns a query into scored results and knows how to activate
+// the items it produced. The result list only deals with providers, so a new
+// source is added by implementing SearchProvider and registering it below.
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::fuzzy::fuzzy_match;
+use crate::app_info::AppInfo;
+use crate::settings::LauncherSettings;
+
+pub mod applications;
+pub mod calculator;
+pub mod commands;
+pub mod conversions;
+pub mod plugins;
+pub mod recent_files;
+
+pub use applications::ApplicationsProvider;
+pub use calculator::CalculatorProvider;
+pub use commands::CommandProvider;
+pub use conversions::ConversionProvider;
+pub use plugins::PluginsProvider;
+pub use recent_files::RecentFilesProvider;
+
+// Secondary fields only match as substrings and rank below typical name matches
+const KEYWORD_SCORE: i64 = 8;
+const DESCRIPTION_SCORE: i64 = 4;
+
+/// A matched item with its rank and the character positions matched in its name
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub item: AppInfo,
+    pub score: i64,
+    pub highlights: Vec<usize>,
+}
+
+pub trait SearchProvider: Send + Sync {
+    /// Name used to enable and order the provider in settings.yaml
+    fn id(&self) -> &'static str;
+
+    /// Results for a lowercased query, best first. Results with equal scores
+    /// keep the order returned here.
+    fn search(&self, query: &str) -> Vec<SearchResult>;
+
+    /// Launch or open an item previously returned by `search`
+    fn activate(&self, item: &AppInfo, settings: &LauncherSettings) -> Result<(), Box<dyn Error>>;
+
+    /// Whether `item` has secondary actions, without computing them
+    fn has_item_actions(&self, item: &AppInfo) -> bool {
+        !item.actions.is_empty()
+    }
+
+    /// Secondary actions of `item`, activated through this provider as well.
+    /// By default these are the desktop actions of an application.
+    fn item_actions(&self, item: &AppInfo) -> Vec<AppInfo> {
+        item.actions
+            .iter()
+            .map(|action| item.action_item(action))
+            .collect()
+    }
+
+    /// Files and directories whose changes make the results stale. Asked again
+    /// after every refresh, as the set can change.
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Bring the results up to date after `changed` paths were created, modified
+    /// or deleted. Runs on a background thread while searches continue.
+    fn refresh(&self, _changed: &[PathBuf]) {}
+
+    /// Providers this one uses without them being searched, e.g. the applications
+    /// offered in "Open with…". They are kept up to date like searched ones.
+    fn dependencies(&self) -> Vec<Arc<dyn SearchProvider>> {
+        Vec::new()
+    }
+}
+
+/// The enabled providers in the order given by `settings.providers`
+pub fn build_providers(settings: &LauncherSettings) -> Vec<Arc<dyn SearchProvider>> {
+    let mut providers: Vec<Arc<dyn SearchProvider>> = Vec::new();
+    // Also used by the recent files for "Open with…", even when not searched itself
+    let mut applications: Option<Arc<ApplicationsProvider>> = None;
+    let mut applications = || {
+        applications
+            .get_or_insert_with(|| Arc::new(ApplicationsProvider::load(settings)))
+            .clone()
+    };
+
+    for provider in settings.providers.iter().filter(|p| p.enabled) {
+        if providers.iter().any(|p| p.id() == provider.name) {
+            continue;
+        }
+        match provider.name.as_str() {
+            applications::ID => providers.push(applications()),
+            recent_files::ID => {
+                let applications = settings.recent_files.enabled.then(&mut applications);
+                providers.push(Arc::new(RecentFilesProvider::load(settings, applications)))
+            }
+            commands::ID => providers.push(Arc::new(CommandProvider)),
+            calculator::ID => providers.push(Arc::new(CalculatorProvider)),
+            conversions::ID => providers.push(Arc::new(ConversionProvider)),
+            plugins::ID => providers.push(Arc::new(PluginsProvider::load())),
+            unknown => eprintln!("Warning: Unknown search provider '{}' in settings", unknown),
+        }
+    }
+
+    providers
+}
+
+/// `providers` and their dependencies, each once: everything to keep up to date
+pub fn watched_providers(providers: &[Arc<dyn SearchProvider>]) -> Vec<Arc<dyn SearchProvider>> {
+    let mut watched: Vec<Arc<dyn SearchProvider>> = Vec::new();
+    for provider in providers
+        .iter()
+        .flat_map(|provider| std::iter::once(provider.clone()).chain(provider.dependencies()))
+    {
+        // The same instance, whatever trait object it is seen through
+        let known = watched
+            .iter()
+            .any(|other| Arc::as_ptr(other).cast::<()>() == Arc::as_ptr(&provider).cast::<()>());
+        if !known {
+            watched.push(provider);
+        }
+    }
+    watched
+}
+
+/// Fuzzy-match the query against the item name, falling back to substring
+/// matches in keywords, categories and the description
+pub fn score_item(item: &AppInfo, query: &str) -> Option<SearchResult> {
+    let (score, highlights) = if let Some(m) = fuzzy_match(query, &item.name) {
+        (m.score, m.positions)
+    } else if item
+        .keywords
+        .iter()
+        .chain(&item.categories)
+        .any(|keyword| keyword.to_lowercase().contains(query))
+    {
+        (KEYWORD_SCORE, Vec::new())
+    } else if item.description.to_lowercase().contains(query) {
+        (DESCRIPTION_SCORE, Vec::new())
+    } else {
+        return None;
+    };
+
+    Some(SearchResult {
+        item: item.clone(),
+        score,
+        highlights,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_info::ItemType;
+    use crate::settings::ProviderSettings;
+
+    fn app(name: &str, keywords: &[&str], description: &str) -> AppInfo {
+        AppInfo {
+            desktop_id: Some(format!("{}.desktop", name.to_lowercase())),
+            name: name.to_string(),
+            description: description.to_string(),
+            exec: name.to_lowercase(),
+            icon: None,
+            categories: Vec::new(),
+            keywords: keywords.iter().map(|k| k.to_string()).collect(),
+            item_type: ItemType::Application,
+            file_path: None,
+            source_path: None,
+            terminal: false,
+            working_dir: None,
+            actions: Vec::new(),
+            mime_types: Vec::new(),
+            mime_type: None,
+            payload: None,
+        }
+    }
+
+    #[test]
+    fn test_score_item_fallbacks() {
+        let firefox = app("Firefox", &["Internet", "WWW"], "Browse the Web");
+        assert!(score_item(&firefox, "ff").unwrap().score > KEYWORD_SCORE);
+        assert_eq!(score_item(&firefox, "www").unwrap().score, KEYWORD_SCORE);
+        assert_eq!(score_item(&firefox, "the web").unwrap().score, DESCRIPTION_SCORE);
+        assert!(score_item(&firefox, "mail").is_none());
+    }
+
+    #[test]
+    fn test_build_providers_follows_settings() {
+        let mut settings = LauncherSettings::default();
+        settings.recent_files.enabled = false;
+        settings.providers = [
+            ("recent_files", true),
+            ("no-such-provider", true),
+            ("applications", false),
+            ("commands", true),
+            ("recent_files", true),
+        ]
+        .iter()
+        .map(|(name, enabled)| ProviderSettings {
+            name: name.to_string(),
+            enabled: *enabled,
+        })
+        .collect();
+
+        let ids: Vec<_> = build_providers(&settings).iter().map(|p| p.id()).collect();
+        assert_eq!(ids, vec!["recent_files", "commands"]);
+    }
+
+    #[test]
+    fn test_applications_ranked_by_score_then_name() {
+        let apps = ["Terminal", "Xterm", "Alacritty Terminal"]
+            .iter()
+            .map(|name| {
+                let app = app(name, &[], "");
+                (app.desktop_id.clone().unwrap(), app)
+            })
+            .collect();
+        let provider = ApplicationsProvider::new(apps);
+
+        let names: Vec<_> = provider
+            .search("term")
+            .into_iter()
+            .map(|r| r.item.name)
+            .collect();
+        assert_eq!(names[0], "Terminal");
+
+        let names: Vec<_> = provider.search("").into_iter().map(|r| r.item.name).collect();
+        assert_eq!(names, vec!["Alacritty Terminal", "Terminal", "Xterm"]);
+    }
+}
@@ -0,0 +1,650 @@
+// plugins.rs - Out-of-process search providers
+//
+// A plugin is any executable declared by a YAML manifest in
+// $XDG_CONFIG_HOME/synaptrix/plugins/. It is started once and kept running; the
+// launcher writes one JSON object per line to its stdin and reads one JSON
+// object per line from its stdout:
+//
+//   -> {"type":"query","id":7,"query":"hello"}
+//   <- {"id":7,"results":[{"title":"Hello","subtitle":"...","icon":"...","action":...}]}
+//   -> {"type":"activate","action":...}
+//
+// `action` is any JSON value; it is handed back unchanged when the user picks
+// the result. Answers that miss the plugin's timeout are dropped, and a plugin
+// that exits is restarted on a later query, so a slow or crashing plugin only
+// loses its own results.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{SearchProvider, SearchResult};
+use crate::app_info::{AppInfo, ItemType};
+use crate::exec;
+use crate::settings::LauncherSettings;
+use crate::xdg;
+
+pub const ID: &str = "plugins";
+
+const DEFAULT_TIMEOUT_MS: u64 = 200;
+// A plugin that exited is not started again sooner than this
+const RESTART_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    pub exec: String, // Command line; relative programs are looked up next to the manifest
+    #[serde(default)]
+    pub keyword: Option<String>, // Only queries starting with "<keyword> " reach the plugin
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+fn default_timeout_ms() -> u64 {
+    DEFAULT_TIMEOUT_MS
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Request<'a> {
+    Query { id: u64, query: &'a str },
+    Activate { action: &'a Value },
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    id: u64,
+    #[serde(default)]
+    results: Vec<PluginResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PluginResult {
+    title: String,
+    #[serde(default)]
+    subtitle: String,
+    #[serde(default)]
+    icon: Option<String>,
+    #[serde(default)]
+    action: Value,
+}
+
+/// Stored in AppInfo::payload to route an activation back to its plugin
+#[derive(Debug, Serialize, Deserialize)]
+struct ActivationPayload {
+    plugin: String,
+    action: Value,
+}
+
+// Queries waiting for an answer, by ID. The reader thread hands each reply to
+// its own query and drops them all when the plugin exits.
+type PendingQueries = Arc<Mutex<HashMap<u64, SyncSender<Vec<PluginResult>>>>>;
+
+struct PluginProcess {
+    child: Child,
+    // Lines for the writer thread, so a plugin that stops reading never blocks us
+    input: Sender<String>,
+    pending: PendingQueries,
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+struct PluginState {
+    process: Option<PluginProcess>,
+    last_start: Option<Instant>,
+    next_id: u64,
+}
+
+pub struct Plugin {
+    manifest: PluginManifest,
+    state: Mutex<PluginState>,
+}
+
+impl Plugin {
+    pub fn new(manifest: PluginManifest) -> Self {
+        Self {
+            manifest,
+            state: Mutex::new(PluginState {
+                process: None,
+                last_start: None,
+                next_id: 0,
+            }),
+        }
+    }
+
+    /// The part of the query meant for this plugin, if any
+    fn plugin_query<'q>(&self, query: &'q str) -> Option<&'q str> {
+        match self.manifest.keyword.as_deref() {
+            Some(keyword) => query
+                .strip_prefix(&keyword.to_lowercase())
+                .and_then(|rest| rest.strip_prefix(' '))
+                .map(str::trim_start),
+            None => Some(query).filter(|q| !q.is_empty()),
+        }
+    }
+
+    /// Send a query, starting the plugin if needed, and return what is needed to
+    /// wait for its answer
+    fn send_query(&self, query: &str) -> Option<PendingQuery> {
+        let mut state = self.state.lock().ok()?;
+        state.next_id += 1;
+        let id = state.next_id;
+        let line = serde_json::to_string(&Request::Query { id, query }).ok()?;
+
+        let (sender, results) = mpsc::sync_channel(1);
+        let pending = self.ensure_started(&mut state)?.pending.clone();
+        pending.lock().ok()?.insert(id, sender);
+        if !self.send_line(&mut state, line) {
+            return None;
+        }
+        Some(PendingQuery {
+            id,
+            results,
+            pending,
+        })
+    }
+
+    /// Wait until `deadline` for the answer to `query`. No lock is held while
+    /// waiting, so other queries and activations are never stuck behind it.
+    fn receive_results(&self, query: PendingQuery, deadline: Instant) -> Vec<PluginResult> {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match query.results.recv_timeout(remaining) {
+            Ok(results) => results,
+            Err(RecvTimeoutError::Timeout) => {
+                // println!("DEBUG: Plugin {} timed out on query {}", self.manifest.name, query.id);
+                if let Ok(mut pending) = query.pending.lock() {
+                    pending.remove(&query.id);
+                }
+                Vec::new()
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                eprintln!("Warning: Plugin {} exited", self.manifest.name);
+                // Unless it was already restarted in the meantime
+                if let Ok(mut state) = self.state.lock() {
+                    let exited = state
+                        .process
+                        .as_ref()
+                        .is_some_and(|p| Arc::ptr_eq(&p.pending, &query.pending));
+                    if exited {
+                        state.process = None;
+                    }
+                }
+                Vec::new()
+            }
+        }
+    }
+
+    fn activate(&self, action: &Value) -> Result<(), Box<dyn Error>> {
+        let line = serde_json::to_string(&Request::Activate { action })?;
+        let mut state = self.state.lock().map_err(|_| "Plugin state is poisoned")?;
+        if self.ensure_started(&mut state).is_some() && self.send_line(&mut state, line) {
+            Ok(())
+        } else {
+            Err(format!("Plugin {} is not running", self.manifest.name).into())
+        }
+    }
+
+    /// The running plugin process, started if it is not running and was not
+    /// started too recently
+    fn ensure_started<'s>(&self, state: &'s mut PluginState) -> Option<&'s PluginProcess> {
+        if state.process.is_none() {
+            let recently_started = state
+                .last_start
+                .is_some_and(|started| started.elapsed() < RESTART_DELAY);
+            if recently_started {
+                return None;
+            }
+            state.last_start = Some(Instant::now());
+            match spawn_plugin(&self.manifest) {
+                Ok(process) => state.process = Some(process),
+                Err(e) => {
+                    eprintln!(
+                        "Warning: Could not start plugin {}: {}",
+                        self.manifest.name, e
+                    );
+                    return None;
+                }
+            }
+        }
+        state.process.as_ref()
+    }
+
+    /// Queue `line` for the plugin's stdin without waiting for it to be written
+    fn send_line(&self, state: &mut PluginState, line: String) -> bool {
+        let Some(process) = state.process.as_ref() else {
+            return false;
+        };
+        if process.input.send(line).is_err() {
+            eprintln!(
+                "Warning: Plugin {} stopped accepting input",
+                self.manifest.name
+            );
+            state.process = None;
+            return false;
+        }
+        true
+    }
+}
+
+/// A query sent to a plugin whose answer has not been received yet
+struct PendingQuery {
+    id: u64,
+    results: Receiver<Vec<PluginResult>>,
+    pending: PendingQueries,
+}
+
+fn spawn_plugin(manifest: &PluginManifest) -> Result<PluginProcess, Box<dyn Error>> {
+    let mut argv = exec::tokenize(&manifest.exec)?;
+    let program = resolve_program(&argv[0], &manifest.dir);
+    argv[0] = program.to_string_lossy().into_owned();
+
+    let mut child = Command::new(&argv[0])
+        .args(&argv[1..])
+        .current_dir(&manifest.dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()?;
+
+    let mut stdin = child.stdin.take().ok_or("Plugin stdin unavailable")?;
+    let stdout = child.stdout.take().ok_or("Plugin stdout unavailable")?;
+
+    // Lines are written on a separate thread so a plugin that stops reading never
+    // blocks the caller; the thread ends when the process is dropped or exits
+    let (input, lines) = mpsc::channel::<String>();
+    let name = manifest.name.clone();
+    thread::spawn(move || {
+        for line in lines {
+            if let Err(e) = writeln!(stdin, "{}", line).and_then(|_| stdin.flush()) {
+                eprintln!("Warning: Plugin {} stopped accepting input: {}", name, e);
+                break;
+            }
+        }
+    });
+
+    // Lines are read on a separate thread so a silent plugin never blocks the caller
+    let pending = PendingQueries::default();
+    let replies = Arc::clone(&pending);
+    let name = manifest.name.clone();
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<Response>(&line) {
+                Ok(response) => {
+                    // A late answer to a query that already timed out has no taker
+                    let sender = replies.lock().ok().and_then(|mut p| p.remove(&response.id));
+                    if let Some(sender) = sender {
+                        let _ = sender.send(response.results);
+                    }
+                }
+                Err(e) => eprintln!("Warning: Plugin {} sent invalid output: {}", name, e),
+            }
+        }
+        // Wake up every query still waiting on the exited plugin
+        if let Ok(mut pending) = replies.lock() {
+            pending.clear();
+        }
+    });
+
+    Ok(PluginProcess {
+        child,
+        input,
+        pending,
+    })
+}
+
+fn resolve_program(program: &str, dir: &Path) -> PathBuf {
+    if let Some(rest) = program.strip_prefix("~/") {
+        return xdg::home_dir().join(rest);
+    }
+    let path = Path::new(program);
+    if path.is_relative() && (program.contains('/') || dir.join(path).is_file()) {
+        return dir.join(path);
+    }
+    path.to_path_buf()
+}
+
+/// ~/.config/synaptrix/plugins (honouring XDG_CONFIG_HOME)
+pub fn plugins_dir() -> PathBuf {
+    xdg::config_home().join("synaptrix").join("plugins")
+}
+
+/// Read every *.yaml / *.yml manifest in `dir`, sorted by file name
+pub fn load_manifests(dir: &Path) -> Vec<PluginManifest> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext == "yaml" || ext == "yml")
+        })
+        .collect();
+    paths.sort();
+
+    paths
+        .iter()
+        .filter_map(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            match serde_yaml::from_str::<PluginManifest>(&content) {
+                Ok(mut manifest) => {
+                    manifest.dir = dir.to_path_buf();
+                    Some(manifest)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Invalid plugin manifest {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .filter(|manifest| manifest.enabled)
+        .collect()
+}
+
+pub struct PluginsProvider {
+    plugins: Vec<Plugin>,
+}
+
+impl PluginsProvider {
+    pub fn load() -> Self {
+        Self::new(load_manifests(&plugins_dir()))
+    }
+
+    pub fn new(manifests: Vec<PluginManifest>) -> Self {
+        Self {
+            plugins: manifests.into_iter().map(Plugin::new).collect(),
+        }
+    }
+}
+
+impl SearchProvider for PluginsProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        // Query every plugin first so their timeouts run concurrently
+        let started = Instant::now();
+        let pending: Vec<(&Plugin, PendingQuery)> = self
+            .plugins
+            .iter()
+            .filter_map(|plugin| {
+                let plugin_query = plugin.plugin_query(query)?;
+                plugin.send_query(plugin_query).map(|sent| (plugin, sent))
+            })
+            .collect();
+
+        let mut results = Vec::new();
+        for (plugin, sent) in pending {
+            let deadline = started + Duration::from_millis(plugin.manifest.timeout_ms);
+            for result in plugin.receive_results(sent, deadline) {
+                let payload = ActivationPayload {
+                    plugin: plugin.manifest.name.clone(),
+                    action: result.action,
+                };
+                results.push(SearchResult {
+                    item: AppInfo {
+                        desktop_id: None,
+                        name: result.title,
+                        description: result.subtitle,
+                        exec: String::new(),
+                        icon: result.icon,
+                        categories: vec![plugin.manifest.name.clone()],
+                        keywords: Vec::new(),
+                        item_type: ItemType::Plugin,
+                        file_path: None,
+                        source_path: None,
+                        terminal: false,
+                        working_dir: None,
+                        actions: Vec::new(),
+                        mime_types: Vec::new(),
+                        mime_type: None,
+                        payload: serde_json::to_string(&payload).ok(),
+                    },
+                    score: 0,
+                    highlights: Vec::new(),
+                });
+            }
+        }
+        results
+    }
+
+    fn activate(&self, item: &AppInfo, _settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        let payload: ActivationPayload =
+            serde_json::from_str(item.payload.as_deref().ok_or("Result has no plugin action")?)?;
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|plugin| plugin.manifest.name == payload.plugin)
+            .ok_or_else(|| format!("Plugin {} is not loaded", payload.plugin))?;
+        plugin.activate(&payload.action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::{tempdir, TempDir};
+
+    // Answers every query with one result echoing the query text, and records
+    // activation requests in ./activated
+    const ECHO_PLUGIN: &str = r#"
+while IFS= read -r line; do
+    case "$line" in
+    *'"type":"query"'*)
+        id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+        query=$(printf '%s' "$line" | sed 's/.*"query":"\([^"]*\)".*/\1/')
+        printf '{"id":%s,"results":[{"title":"Echo %s","subtitle":"sub","icon":"face-smile","action":{"text":"%s"}}]}\n' "$id" "$query" "$query"
+        ;;
+    *'"type":"activate"'*)
+        printf '%s\n' "$line" > activated.tmp && mv activated.tmp activated
+        ;;
+    esac
+done
+"#;
+
+    fn plugin_dir(script: &str) -> TempDir {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("plugin.sh");
+        fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        dir
+    }
+
+    fn manifest(dir: &Path, keyword: Option<&str>, timeout_ms: u64) -> PluginManifest {
+        PluginManifest {
+            name: "echo".to_string(),
+            exec: "plugin.sh".to_string(),
+            keyword: keyword.map(String::from),
+            timeout_ms,
+            enabled: true,
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn titles(results: &[SearchResult]) -> Vec<String> {
+        results.iter().map(|r| r.item.name.clone()).collect()
+    }
+
+    #[test]
+    fn test_query_round_trip_and_activation() {
+        let dir = plugin_dir(ECHO_PLUGIN);
+        let provider = PluginsProvider::new(vec![manifest(dir.path(), None, 2000)]);
+
+        let results = provider.search("hello");
+        assert_eq!(titles(&results), vec!["Echo hello"]);
+        let item = &results[0].item;
+        assert_eq!(item.description, "sub");
+        assert_eq!(item.icon.as_deref(), Some("face-smile"));
+        assert_eq!(item.item_type, ItemType::Plugin);
+
+        // The process is reused for later queries
+        assert_eq!(titles(&provider.search("again")), vec!["Echo again"]);
+        assert!(provider.search("").is_empty());
+
+        provider
+            .activate(item, &LauncherSettings::default())
+            .unwrap();
+        let activated = dir.path().join("activated");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !activated.exists() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(
+            fs::read_to_string(activated).unwrap().trim(),
+            r#"{"type":"activate","action":{"text":"hello"}}"#
+        );
+    }
+
+    #[test]
+    fn test_keyword_prefix() {
+        let dir = plugin_dir(ECHO_PLUGIN);
+        let provider = PluginsProvider::new(vec![manifest(dir.path(), Some("Echo"), 2000)]);
+
+        assert!(provider.search("hello").is_empty());
+        assert!(provider.search("echoes").is_empty());
+        assert_eq!(titles(&provider.search("echo  world")), vec!["Echo world"]);
+    }
+
+    #[test]
+    fn test_slow_plugin_times_out() {
+        let dir = plugin_dir("while read -r line; do sleep 5; done");
+        let provider = PluginsProvider::new(vec![manifest(dir.path(), None, 100)]);
+
+        let started = Instant::now();
+        assert!(provider.search("hello").is_empty());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_activation_does_not_wait_for_pending_query() {
+        let dir = plugin_dir("while read -r line; do sleep 5; done");
+        let provider = Arc::new(PluginsProvider::new(vec![manifest(dir.path(), None, 3000)]));
+
+        let searching = Arc::clone(&provider);
+        let search = thread::spawn(move || searching.search("hello"));
+        thread::sleep(Duration::from_millis(300));
+
+        let plugin = &provider.plugins[0];
+        let started = Instant::now();
+        plugin.activate(&Value::Null).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(search.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stale_query_does_not_take_newer_answer() {
+        // Never answers "slow", answers everything else right away
+        let dir = plugin_dir(
+            r#"
+while IFS= read -r line; do
+    case "$line" in
+    *'"query":"slow"'*) ;;
+    *)
+        id=$(printf '%s' "$line" | sed 's/.*"id":\([0-9]*\).*/\1/')
+        printf '{"id":%s,"results":[{"title":"Echo fast"}]}\n' "$id"
+        ;;
+    esac
+done
+"#,
+        );
+        let provider = Arc::new(PluginsProvider::new(vec![manifest(dir.path(), None, 3000)]));
+
+        let searching = Arc::clone(&provider);
+        let stale = thread::spawn(move || searching.search("slow"));
+        thread::sleep(Duration::from_millis(300));
+
+        let started = Instant::now();
+        assert_eq!(titles(&provider.search("fast")), vec!["Echo fast"]);
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(stale.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_activation_does_not_block_on_full_pipe() {
+        let dir = plugin_dir("sleep 30");
+        let provider = PluginsProvider::new(vec![manifest(dir.path(), None, 100)]);
+        let plugin = &provider.plugins[0];
+
+        // Far more than a pipe buffer, none of which is ever read
+        let action = Value::String("x".repeat(64 * 1024));
+        let started = Instant::now();
+        for _ in 0..4 {
+            plugin.activate(&action).unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_crashing_and_invalid_plugins_are_isolated() {
+        let crashing = plugin_dir("exit 1");
+        let garbage = plugin_dir("while read -r line; do echo 'not json'; done");
+        let echo = plugin_dir(ECHO_PLUGIN);
+        let missing = tempdir().unwrap();
+
+        let provider = PluginsProvider::new(vec![
+            manifest(crashing.path(), None, 500),
+            manifest(garbage.path(), None, 500),
+            manifest(missing.path(), None, 500),
+            manifest(echo.path(), None, 2000),
+        ]);
+
+        assert_eq!(titles(&provider.search("hi")), vec!["Echo hi"]);
+        // A crashed plugin is not restarted in a tight loop
+        assert_eq!(titles(&provider.search("hi")), vec!["Echo hi"]);
+    }
+
+    #[test]
+    fn test_load_manifests() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("b.yaml"),
+            "name: Dictionary\nexec: ./dict.py --fast\nkeyword: def\ntimeout_ms: 500\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("a.yml"), "name: Minimal\nexec: minimal\n").unwrap();
+        fs::write(dir.path().join("c.yaml"), "name: Off\nexec: off\nenabled: false\n").unwrap();
+        fs::write(dir.path().join("broken.yaml"), "exec: [").unwrap();
+        fs::write(dir.path().join("notes.txt"), "name: Ignored\nexec: x\n").unwrap();
+
+        let manifests = load_manifests(dir.path());
+        let names: Vec<_> = manifests.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["Minimal", "Dictionary"]);
+        assert_eq!(manifests[0].keyword, None);
+        assert_eq!(manifests[0].timeout_ms, DEFAULT_TIMEOUT_MS);
+        assert_eq!(manifests[1].keyword.as_deref(), Some("def"));
+        assert_eq!(manifests[1].timeout_ms, 500);
+        assert_eq!(manifests[1].dir, dir.path());
+
+        assert_eq!(resolve_program("./dict.py", dir.path()), dir.path().join("./dict.py"));
+        assert_eq!(resolve_program("python3", dir.path()), PathBuf::from("python3"));
+    }
+}
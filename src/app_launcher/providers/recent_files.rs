@@ -0,0 +1,325 @@
+// recent_files.rs - Recently used and recently modified files
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+
+use super::{score_item, ApplicationsProvider, SearchProvider, SearchResult};
+use crate::app_info::{AppInfo, ItemType};
+use crate::app_launcher::file_index::{FileIndex, IndexOptions};
+use crate::app_launcher::file_loader;
+use crate::file_actions::FileAction;
+use crate::mime;
+use crate::settings::LauncherSettings;
+use crate::utils::{add_to_recent_files, launch_application_with_files, open_file};
+
+pub const ID: &str = "recent_files";
+
+pub struct RecentFilesProvider {
+    files: RwLock<Vec<AppInfo>>,                     // Newest first
+    source: Option<Mutex<FileSource>>,               // None for a fixed list of files
+    applications: Option<Arc<ApplicationsProvider>>, // Offered in "Open with…"
+}
+
+/// Where the files come from, kept to refresh them
+struct FileSource {
+    index: FileIndex,
+    index_path: PathBuf,
+    settings: LauncherSettings,
+}
+
+impl RecentFilesProvider {
+    pub fn load(
+        settings: &LauncherSettings,
+        applications: Option<Arc<ApplicationsProvider>>,
+    ) -> Self {
+        if !settings.recent_files.enabled {
+            // println!("DEBUG: Recent files disabled in settings");
+            return Self::new(Vec::new());
+        }
+        Self::with_index(
+            IndexOptions::from_settings(&settings.recent_files),
+            FileIndex::default_path(),
+            settings,
+            applications,
+        )
+    }
+
+    /// Index `options` using the saved index at `index_path`
+    pub fn with_index(
+        options: IndexOptions,
+        index_path: PathBuf,
+        settings: &LauncherSettings,
+        applications: Option<Arc<ApplicationsProvider>>,
+    ) -> Self {
+        let index = FileIndex::load_or_build(options, &index_path);
+        let mut files = Vec::new();
+        file_loader::load_recent_files(&mut files, &index, settings);
+        Self {
+            files: RwLock::new(files),
+            source: Some(Mutex::new(FileSource {
+                index,
+                index_path,
+                settings: settings.clone(),
+            })),
+            applications,
+        }
+    }
+
+    pub fn new(files: Vec<AppInfo>) -> Self {
+        Self {
+            files: RwLock::new(files),
+            source: None,
+            applications: None,
+        }
+    }
+}
+
+/// An "Open with…" entry launching `app` with the file at `path`
+fn open_with_item(app: &AppInfo, path: &Path) -> AppInfo {
+    let file_name = path.file_name().unwrap_or(path.as_os_str());
+    AppInfo {
+        name: format!("Open with {}", app.name),
+        description: format!("Open {} with {}", file_name.to_string_lossy(), app.name),
+        file_path: Some(path.to_path_buf()),
+        actions: Vec::new(),
+        ..app.clone()
+    }
+}
+
+impl SearchProvider for RecentFilesProvider {
+    fn id(&self) -> &'static str {
+        ID
+    }
+
+    fn search(&self, query: &str) -> Vec<SearchResult> {
+        let Ok(files) = self.files.read() else {
+            return Vec::new();
+        };
+        let mut results: Vec<SearchResult> = files
+            .iter()
+            .filter_map(|file| score_item(file, query))
+            .collect();
+        // println!("DEBUG: Matched {} of {} recent files", results.len(), files.len());
+
+        // Stable sort, so equally good files stay newest first
+        results.sort_by_key(|result| std::cmp::Reverse(result.score));
+        results
+    }
+
+    fn activate(&self, item: &AppInfo, settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        let Some(ref path) = item.file_path else {
+            return Ok(());
+        };
+        if item.item_type == ItemType::FileAction {
+            let action = item
+                .payload
+                .as_deref()
+                .and_then(FileAction::from_id)
+                .ok_or("Unknown file action")?;
+            action.run(path, settings)?;
+            if action == FileAction::MoveToTrash {
+                // Gone right away, before the watcher notices
+                if let Ok(mut files) = self.files.write() {
+                    files.retain(|file| file.file_path.as_ref() != Some(path));
+                }
+            }
+            return Ok(());
+        }
+
+        // "Open with…" entries pass the file to the chosen application's Exec line
+        let app = (item.item_type == ItemType::Application).then_some(item);
+        match app {
+            Some(app) => launch_application_with_files(app, std::slice::from_ref(path), settings)?,
+            None => {
+                // println!("DEBUG: Opening file: {:?}", path);
+                open_file(path)?
+            }
+        }
+        if settings.recent_files.record_opened {
+            add_to_recent_files(path, app, settings);
+        }
+        Ok(())
+    }
+
+    fn has_item_actions(&self, item: &AppInfo) -> bool {
+        item.item_type == ItemType::RecentFile && item.file_path.is_some()
+    }
+
+    /// The file actions, then "Open with…" each application that handles the file,
+    /// including those for the types its type is a kind of
+    fn item_actions(&self, item: &AppInfo) -> Vec<AppInfo> {
+        if !self.has_item_actions(item) {
+            return Vec::new();
+        }
+        let mut actions: Vec<AppInfo> = FileAction::ALL
+            .iter()
+            .map(|action| action.item(item))
+            .collect();
+        if let (Some(applications), Some(path)) = (&self.applications, &item.file_path) {
+            let database = mime::database();
+            let mime_type = item
+                .mime_type
+                .clone()
+                .unwrap_or_else(|| database.detect(path));
+            actions.extend(
+                applications
+                    .apps_for_mime_types(&database.ancestors(&mime_type))
+                    .iter()
+                    .map(|app| open_with_item(app, path)),
+            );
+        }
+        actions
+    }
+
+    fn watched_paths(&self) -> Vec<PathBuf> {
+        let Some(Ok(source)) = self.source.as_ref().map(|source| source.lock()) else {
+            return Vec::new();
+        };
+        let mut paths = source.index.directories();
+        paths.push(PathBuf::from(&source.settings.recent_files.xbel_path));
+        paths
+    }
+
+    fn dependencies(&self) -> Vec<Arc<dyn SearchProvider>> {
+        self.applications
+            .iter()
+            .map(|applications| applications.clone() as Arc<dyn SearchProvider>)
+            .collect()
+    }
+
+    fn refresh(&self, changed: &[PathBuf]) {
+        let Some(Ok(mut source)) = self.source.as_ref().map(|source| source.lock()) else {
+            return;
+        };
+        let source = &mut *source;
+
+        // Applications rewrite the XBEL file on every open; that alone needs no re-index
+        let xbel_path = PathBuf::from(&source.settings.recent_files.xbel_path);
+        if changed.iter().any(|path| *path != xbel_path) {
+            source.index.update(&source.index_path);
+        }
+
+        let mut files = Vec::new();
+        file_loader::load_recent_files(&mut files, &source.index, &source.settings);
+        if let Ok(mut current) = self.files.write() {
+            *current = files;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_launcher::providers::watched_providers;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_refresh_picks_up_new_files() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("Documents");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("report.txt"), "").unwrap();
+
+        let mut settings = LauncherSettings::default();
+        settings.recent_files.directories = vec![root.display().to_string()];
+        settings.recent_files.xbel_path =
+            dir.path().join("recently-used.xbel").display().to_string();
+        let provider = RecentFilesProvider::with_index(
+            IndexOptions::from_settings(&settings.recent_files),
+            dir.path().join("file_index.json"),
+            &settings,
+            None,
+        );
+        assert_eq!(provider.search("").len(), 1);
+
+        let watched = provider.watched_paths();
+        assert!(watched.contains(&root));
+        assert!(watched.contains(&dir.path().join("recently-used.xbel")));
+
+        // New subdirectories are indexed and watched too
+        fs::create_dir_all(root.join("2024")).unwrap();
+        fs::write(root.join("2024/taxes.pdf"), "").unwrap();
+        provider.refresh(&[root.join("2024")]);
+        assert_eq!(provider.search("taxes").len(), 1);
+        assert!(provider.watched_paths().contains(&root.join("2024")));
+
+        fs::remove_file(root.join("report.txt")).unwrap();
+        provider.refresh(&[root.join("report.txt")]);
+        assert!(provider.search("report").is_empty());
+    }
+
+    #[test]
+    fn test_open_with_lists_applications_for_the_file() {
+        let dir = tempdir().unwrap();
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, "").unwrap();
+        let file = file_loader::file_item(&notes).unwrap();
+
+        let mut editor = file.clone();
+        editor.desktop_id = Some("editor.desktop".to_string());
+        editor.name = "Editor".to_string();
+        editor.exec = "editor %f".to_string();
+        editor.item_type = ItemType::Application;
+        editor.file_path = None;
+        editor.mime_types = vec!["text/plain".to_string()];
+        let applications = ApplicationsProvider::new(
+            [("editor.desktop".to_string(), editor)]
+                .into_iter()
+                .collect(),
+        );
+
+        let mut provider = RecentFilesProvider::new(vec![file.clone()]);
+        assert_eq!(provider.item_actions(&file).len(), FileAction::ALL.len());
+        provider.applications = Some(Arc::new(applications));
+
+        let actions = provider.item_actions(&file);
+        let open_with = actions.last().unwrap();
+        assert_eq!(actions.len(), FileAction::ALL.len() + 1);
+        assert_eq!(open_with.name, "Open with Editor");
+        assert_eq!(open_with.description, "Open notes.txt with Editor");
+        assert_eq!(open_with.file_path.as_ref(), Some(&notes));
+        assert_eq!(open_with.item_type, ItemType::Application);
+        // Actions have no actions of their own
+        assert!(actions
+            .iter()
+            .all(|action| !provider.has_item_actions(action)));
+    }
+
+    #[test]
+    fn test_open_with_applications_are_watched() {
+        let applications = Arc::new(ApplicationsProvider::new(Default::default()));
+        let mut provider = RecentFilesProvider::new(Vec::new());
+        provider.applications = Some(applications.clone());
+        let provider: Arc<dyn SearchProvider> = Arc::new(provider);
+
+        // Watched even when not searched themselves, and only once when they are
+        let ids = |providers: &[Arc<dyn SearchProvider>]| -> Vec<&'static str> {
+            watched_providers(providers)
+                .iter()
+                .map(|p| p.id())
+                .collect()
+        };
+        assert_eq!(
+            ids(std::slice::from_ref(&provider)),
+            vec![ID, "applications"]
+        );
+        assert_eq!(ids(&[provider, applications]), vec![ID, "applications"]);
+    }
+
+    #[test]
+    fn test_unknown_file_action_is_rejected() {
+        let dir = tempdir().unwrap();
+        let notes = dir.path().join("notes.txt");
+        fs::write(&notes, "").unwrap();
+        let file = file_loader::file_item(&notes).unwrap();
+        let provider = RecentFilesProvider::new(vec![file.clone()]);
+
+        let mut bogus = FileAction::MoveToTrash.item(&file);
+        bogus.payload = Some("shred".to_string());
+        assert!(provider
+            .activate(&bogus, &LauncherSettings::default())
+            .is_err());
+        assert_eq!(provider.search("notes").len(), 1);
+    }
+}
@@ -0,0 +1,456 @@
+// result_list.rs - Virtualized list of search results
+//
+// Results live in a gio::ListStore shown by a GtkListView. The view only
+// creates widgets for the rows that are visible and rebinds them while
+// scrolling, so replacing thousands of results costs one model update.
+use gtk::prelude::*;
+use gtk::Box as GtkBox;
+use gtk::{gio, glib};
+use gtk::{Button, Image, Label, ListItem, ListView, SignalListItemFactory, SingleSelection};
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
+
+use super::providers::{SearchProvider, SearchResult};
+use crate::app_info::{AppInfo, ItemType};
+use crate::history::{self, LaunchHistory};
+use crate::settings::LauncherSettings;
+use crate::utils::create_icon_widget;
+
+/// One row of the list: a result and where it came from
+pub struct ResultRow {
+    pub provider: Arc<dyn SearchProvider>,
+    pub result: SearchResult,
+    pub query: String,         // Query the result was found for, recorded on launch
+    pub section_start: bool,   // First row of a new item type
+}
+
+#[derive(Clone)]
+pub struct ResultList {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    view: ListView,
+    store: gio::ListStore,
+    selection: SingleSelection,
+    error_banner: GtkBox,
+    error_title: Label,
+    error_detail: Label,
+    actions_mode: Cell<bool>,
+    history: Rc<RefCell<LaunchHistory>>,
+    settings: LauncherSettings,
+}
+
+impl ResultList {
+    pub fn new(history: Rc<RefCell<LaunchHistory>>, settings: &LauncherSettings) -> Self {
+        let store = gio::ListStore::new::<glib::BoxedAnyObject>();
+        let selection = SingleSelection::new(Some(store.clone()));
+        selection.set_autoselect(false);
+        selection.set_can_unselect(true);
+
+        let factory = SignalListItemFactory::new();
+        let view = ListView::new(Some(selection.clone()), Some(factory.clone()));
+        view.add_css_class("app-list");
+
+        let (error_banner, error_title, error_detail) = build_error_banner();
+
+        let list = Self {
+            inner: Rc::new(Inner {
+                view,
+                store,
+                selection,
+                error_banner,
+                error_title,
+                error_detail,
+                actions_mode: Cell::new(false),
+                history,
+                settings: settings.clone(),
+            }),
+        };
+
+        // The view owns the factory, so its handlers only hold a weak reference to the list
+        let weak = Rc::downgrade(&list.inner);
+        factory.connect_setup(move |_, object| {
+            let Some(list_item) = object.downcast_ref::<ListItem>() else {
+                return;
+            };
+            let row = RowWidgets::new();
+            row.connect_buttons(list_item, weak.clone());
+            list_item.set_child(Some(&row.container));
+        });
+
+        let show_descriptions = settings.behavior.show_descriptions;
+        factory.connect_bind(move |_, object| {
+            let Some(list_item) = object.downcast_ref::<ListItem>() else {
+                return;
+            };
+            let (Some(row), Some(object)) = (
+                list_item.child().and_then(|child| RowWidgets::from_child(&child)),
+                list_item.item().and_downcast::<glib::BoxedAnyObject>(),
+            ) else {
+                return;
+            };
+            row.bind(&object.borrow::<ResultRow>(), show_descriptions);
+        });
+
+        let weak = Rc::downgrade(&list.inner);
+        list.inner.view.connect_activate(move |_, position| {
+            if let Some(inner) = weak.upgrade() {
+                ResultList { inner }.activate(position);
+            }
+        });
+
+        list
+    }
+
+    pub fn view(&self) -> &ListView {
+        &self.inner.view
+    }
+
+    /// Inline launch errors, shown above the results
+    pub fn error_banner(&self) -> &GtkBox {
+        &self.inner.error_banner
+    }
+
+    /// Replace all rows at once; the first row is selected when `select_first` is set
+    pub fn set_results(&self, rows: Vec<ResultRow>, select_first: bool) {
+        let objects: Vec<glib::BoxedAnyObject> =
+            rows.into_iter().map(glib::BoxedAnyObject::new).collect();
+        let inner = &self.inner;
+        inner.store.splice(0, inner.store.n_items(), &objects);
+        inner.actions_mode.set(false);
+        inner.error_banner.set_visible(false);
+
+        if select_first && !objects.is_empty() {
+            self.select(0);
+        } else {
+            inner.selection.set_selected(gtk::INVALID_LIST_POSITION);
+        }
+    }
+
+    /// Whether the list shows the secondary actions of an item
+    pub fn in_actions_mode(&self) -> bool {
+        self.inner.actions_mode.get()
+    }
+
+    pub fn select(&self, position: u32) {
+        let inner = &self.inner;
+        if position >= inner.store.n_items() {
+            return;
+        }
+        inner.selection.set_selected(position);
+        let _ = inner
+            .view
+            .activate_action("list.scroll-to-item", Some(&position.to_variant()));
+    }
+
+    /// Move the selection up (negative) or down, starting at the top when nothing is selected
+    pub fn move_selection(&self, offset: i32) {
+        let n_items = self.inner.store.n_items();
+        if n_items == 0 {
+            return;
+        }
+        let selected = self.inner.selection.selected();
+        let target = if selected == gtk::INVALID_LIST_POSITION {
+            0
+        } else {
+            (selected as i64 + offset as i64).clamp(0, n_items as i64 - 1) as u32
+        };
+        self.select(target);
+    }
+
+    /// Launch the selected row, or the first one when nothing is selected
+    pub fn activate_selected(&self) {
+        let selected = self.inner.selection.selected();
+        if selected != gtk::INVALID_LIST_POSITION {
+            self.activate(selected);
+        } else if self.inner.store.n_items() > 0 {
+            self.select(0);
+            self.activate(0);
+        }
+    }
+
+    /// Show the secondary actions of the selected item: the desktop actions of an
+    /// application, or the file actions and "Open with…" for a file. Returns false if
+    /// it has none.
+    pub fn show_selected_actions(&self) -> bool {
+        let selected = self.inner.selection.selected();
+        let Some((provider, app, query)) = self.row_at(selected) else {
+            return false;
+        };
+        let actions = provider.item_actions(&app);
+        if actions.is_empty() {
+            return false;
+        }
+        self.show_actions(provider, actions, &query);
+        true
+    }
+
+    /// Replace the results with secondary actions ("New Private Window", ...), which
+    /// are activated through the provider of the item they belong to.
+    /// The normal results come back when the search entry changes or on Left/Escape.
+    fn show_actions(&self, provider: Arc<dyn SearchProvider>, actions: Vec<AppInfo>, query: &str) {
+        let rows = actions
+            .into_iter()
+            .map(|item| ResultRow {
+                provider: provider.clone(),
+                result: SearchResult {
+                    item,
+                    score: 0,
+                    highlights: Vec::new(),
+                },
+                query: query.to_string(),
+                section_start: false,
+            })
+            .collect();
+        self.set_results(rows, true);
+        self.inner.actions_mode.set(true);
+    }
+
+    fn row_at(&self, position: u32) -> Option<(Arc<dyn SearchProvider>, AppInfo, String)> {
+        let object = self
+            .inner
+            .store
+            .item(position)
+            .and_downcast::<glib::BoxedAnyObject>()?;
+        let row = object.borrow::<ResultRow>();
+        Some((row.provider.clone(), row.result.item.clone(), row.query.clone()))
+    }
+
+    fn activate(&self, position: u32) {
+        let Some((provider, item, query)) = self.row_at(position) else {
+            return;
+        };
+        let inner = &self.inner;
+
+        // Keep the window open so the error stays visible
+        if let Err(e) = provider.activate(&item, &inner.settings) {
+            eprintln!("{}", e);
+            self.show_error(&item.name, &e.to_string());
+            return;
+        }
+        inner.error_banner.set_visible(false);
+
+        if inner.settings.history.enabled {
+            if let Some(id) = item.history_id() {
+                inner.history.borrow_mut().record(&id, &query, history::now());
+            }
+        }
+
+        if inner.settings.behavior.auto_close {
+            if let Some(window) = inner
+                .view
+                .root()
+                .and_then(|root| root.downcast::<gtk::ApplicationWindow>().ok())
+            {
+                window.close();
+            }
+        }
+    }
+
+    /// Show a launch failure above the result list
+    pub fn show_error(&self, item_name: &str, message: &str) {
+        let inner = &self.inner;
+        inner
+            .error_title
+            .set_text(&format!("Could not launch {}", item_name));
+        inner.error_detail.set_text(message);
+        inner.error_banner.set_visible(true);
+    }
+}
+
+fn build_error_banner() -> (GtkBox, Label, Label) {
+    let banner = GtkBox::new(gtk::Orientation::Horizontal, 12);
+    banner.add_css_class("error-row");
+    banner.set_visible(false);
+
+    let icon = Image::from_icon_name("dialog-error");
+    icon.set_pixel_size(32);
+    banner.append(&icon);
+
+    let vbox = GtkBox::new(gtk::Orientation::Vertical, 4);
+    vbox.set_hexpand(true);
+    vbox.set_valign(gtk::Align::Center);
+
+    let title = Label::new(None);
+    title.set_halign(gtk::Align::Start);
+    title.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    title.add_css_class("app-name");
+    vbox.append(&title);
+
+    let detail = Label::new(None);
+    detail.set_halign(gtk::Align::Start);
+    detail.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    detail.set_max_width_chars(60);
+    detail.add_css_class("app-description");
+    vbox.append(&detail);
+
+    banner.append(&vbox);
+    (banner, title, detail)
+}
+
+/// The widgets of one recycled row
+struct RowWidgets {
+    container: GtkBox,
+    icon_slot: GtkBox,
+    name: Label,
+    description: Label,
+    launch: Button,
+    actions: Button,
+}
+
+impl RowWidgets {
+    fn new() -> Self {
+        let container = GtkBox::new(gtk::Orientation::Horizontal, 12);
+        container.set_margin_top(8);
+        container.set_margin_bottom(8);
+        container.set_margin_start(8);
+        container.set_margin_end(8);
+
+        // Holds a fresh icon per bind, so a thumbnail finishing late can't land on a reused row
+        let icon_slot = GtkBox::new(gtk::Orientation::Horizontal, 0);
+        container.append(&icon_slot);
+
+        let vbox = GtkBox::new(gtk::Orientation::Vertical, 4);
+        vbox.set_hexpand(true);
+        vbox.set_valign(gtk::Align::Center);
+
+        let name = Label::new(None);
+        name.set_halign(gtk::Align::Start);
+        name.set_max_width_chars(60); // Adjust this value as needed
+        name.add_css_class("app-name");
+        vbox.append(&name);
+
+        let description = Label::new(None);
+        description.set_halign(gtk::Align::Start);
+        description.set_ellipsize(gtk::pango::EllipsizeMode::End);
+        description.set_max_width_chars(60);
+        description.add_css_class("app-description");
+        vbox.append(&description);
+        container.append(&vbox);
+
+        let launch = Button::with_label("Launch");
+        launch.add_css_class("launch-button");
+        launch.set_valign(gtk::Align::Center);
+        container.append(&launch);
+
+        // Applications with desktop actions and files get a button opening the actions list
+        let actions = Button::from_icon_name("go-next-symbolic");
+        actions.add_css_class("actions-button");
+        actions.set_valign(gtk::Align::Center);
+        container.append(&actions);
+
+        Self {
+            container,
+            icon_slot,
+            name,
+            description,
+            launch,
+            actions,
+        }
+    }
+
+    /// Recover the widgets from a row container built by `new`
+    fn from_child(child: &gtk::Widget) -> Option<Self> {
+        let container = child.downcast_ref::<GtkBox>()?.clone();
+        let icon_slot = container.first_child()?;
+        let vbox = icon_slot.next_sibling()?;
+        let launch = vbox.next_sibling()?;
+        let actions = launch.next_sibling()?;
+        let name = vbox.first_child()?;
+        let description = name.next_sibling()?;
+
+        Some(Self {
+            container,
+            icon_slot: icon_slot.downcast().ok()?,
+            name: name.downcast().ok()?,
+            description: description.downcast().ok()?,
+            launch: launch.downcast().ok()?,
+            actions: actions.downcast().ok()?,
+        })
+    }
+
+    fn connect_buttons(&self, list_item: &ListItem, list: Weak<Inner>) {
+        let item_weak = list_item.downgrade();
+        let launch_list = list.clone();
+        self.launch.connect_clicked(move |_| {
+            if let (Some(list_item), Some(inner)) = (item_weak.upgrade(), launch_list.upgrade()) {
+                ResultList { inner }.activate(list_item.position());
+            }
+        });
+
+        let item_weak = list_item.downgrade();
+        self.actions.connect_clicked(move |_| {
+            if let (Some(list_item), Some(inner)) = (item_weak.upgrade(), list.upgrade()) {
+                let results = ResultList { inner };
+                results.select(list_item.position());
+                results.show_selected_actions();
+            }
+        });
+    }
+
+    fn bind(&self, row: &ResultRow, show_descriptions: bool) {
+        let item = &row.result.item;
+
+        for class in ["app-row", "command-row", "file-row", "section-start"] {
+            self.container.remove_css_class(class);
+        }
+        self.container.add_css_class(match item.item_type {
+            ItemType::Command => "command-row",
+            ItemType::RecentFile => "file-row",
+            _ => "app-row",
+        });
+        // Add section separator class if this is the first item of a new type
+        if row.section_start {
+            self.container.add_css_class("section-start");
+        }
+
+        while let Some(child) = self.icon_slot.first_child() {
+            self.icon_slot.remove(&child);
+        }
+        self.icon_slot.append(&create_icon_widget(item));
+
+        self.name
+            .set_markup(&highlight_markup(&item.name, &row.result.highlights));
+        // Use middle ellipsize for files to preserve extensions, end for others
+        self.name.set_ellipsize(match item.item_type {
+            ItemType::RecentFile => gtk::pango::EllipsizeMode::Middle,
+            _ => gtk::pango::EllipsizeMode::End,
+        });
+
+        self.description.set_text(&item.description);
+        self.description
+            .set_visible(show_descriptions && !item.description.is_empty());
+
+        self.launch.set_label(match item.item_type {
+            ItemType::Command | ItemType::FileAction => "Run",
+            ItemType::RecentFile => "Open",
+            ItemType::Calculator => "Copy",
+            _ => "Launch",
+        });
+        self.actions.set_visible(row.provider.has_item_actions(item));
+        self.actions.set_tooltip_text(Some(match item.item_type {
+            ItemType::RecentFile => "File actions and Open with… (Right or Tab)",
+            _ => "Show actions (Right or Tab)",
+        }));
+    }
+}
+
+/// Pango markup for `text` with the characters at `positions` underlined
+fn highlight_markup(text: &str, positions: &[usize]) -> String {
+    let mut markup = String::new();
+    let mut highlighted = false;
+    for (i, c) in text.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != highlighted {
+            markup.push_str(if matched { "<u>" } else { "</u>" });
+            highlighted = matched;
+        }
+        markup.push_str(&glib::markup_escape_text(c.encode_utf8(&mut [0; 4])));
+    }
+    if highlighted {
+        markup.push_str("</u>");
+    }
+    markup
+}
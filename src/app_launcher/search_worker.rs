@@ -0,0 +1,223 @@
+// search_worker.rs - Run searches off the GTK main thread
+//
+// Every keystroke starts a new search generation. Providers run on a tokio
+// runtime, concurrently with each other. Once a newer generation exists, an
+// older search starts no further work and whatever it already found is
+// dropped. Finished searches are sent back over a channel the main loop reads.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::providers::{SearchProvider, SearchResult};
+
+// Keystrokes closer together than this only search for the last one
+const DEBOUNCE: Duration = Duration::from_millis(30);
+
+/// The results of one search, grouped by provider in provider order
+pub struct SearchBatch {
+    pub generation: u64,
+    pub query: String,
+    pub results: Vec<(Arc<dyn SearchProvider>, Vec<SearchResult>)>,
+}
+
+pub struct SearchWorker {
+    runtime: Runtime,
+    providers: Vec<Arc<dyn SearchProvider>>,
+    generation: Arc<AtomicU64>,
+    sender: UnboundedSender<SearchBatch>,
+}
+
+impl SearchWorker {
+    pub fn new(
+        providers: Vec<Arc<dyn SearchProvider>>,
+    ) -> Result<(Self, UnboundedReceiver<SearchBatch>), std::io::Error> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("synaptrix-search")
+            .enable_all()
+            .build()?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        let worker = Self {
+            runtime,
+            providers,
+            generation: Arc::new(AtomicU64::new(0)),
+            sender,
+        };
+        Ok((worker, receiver))
+    }
+
+    /// Start searching for `query`, superseding any search still in progress.
+    /// Returns the generation the results will be tagged with.
+    pub fn search(&self, query: &str) -> u64 {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let current = self.generation.clone();
+        let providers = self.providers.clone();
+        let sender = self.sender.clone();
+        let query = query.to_string();
+
+        self.runtime.spawn(async move {
+            let is_current = || current.load(Ordering::SeqCst) == generation;
+
+            // An empty query (the window being shown) is answered right away
+            if !query.is_empty() {
+                tokio::time::sleep(DEBOUNCE).await;
+            }
+            if !is_current() {
+                return;
+            }
+
+            // Providers may block (file systems, plugin processes), so each
+            // gets its own blocking thread and they all run at once
+            let handles: Vec<_> = providers
+                .iter()
+                .map(|provider| {
+                    let provider = provider.clone();
+                    let query = query.clone();
+                    tokio::task::spawn_blocking(move || provider.search(&query))
+                })
+                .collect();
+
+            let mut results = Vec::with_capacity(providers.len());
+            for (provider, handle) in providers.into_iter().zip(handles) {
+                let provider_results = handle.await.unwrap_or_else(|e| {
+                    eprintln!("Search provider {} failed: {}", provider.id(), e);
+                    Vec::new()
+                });
+                if !is_current() {
+                    // println!("DEBUG: Dropping stale search for '{}'", query);
+                    return;
+                }
+                results.push((provider, provider_results));
+            }
+
+            let _ = sender.send(SearchBatch {
+                generation,
+                query,
+                results,
+            });
+        });
+
+        generation
+    }
+
+    pub fn providers(&self) -> &[Arc<dyn SearchProvider>] {
+        &self.providers
+    }
+
+    /// Whether `generation` is still the latest search
+    pub fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_info::AppInfo;
+    use crate::app_launcher::providers::CommandProvider;
+    use crate::settings::LauncherSettings;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Instant;
+
+    /// Echoes the query back after a delay, counting how often it was asked
+    struct SlowProvider {
+        delay: Duration,
+        calls: AtomicUsize,
+    }
+
+    impl SearchProvider for SlowProvider {
+        fn id(&self) -> &'static str {
+            "slow"
+        }
+
+        fn search(&self, query: &str) -> Vec<SearchResult> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            std::thread::sleep(self.delay);
+            let mut item = CommandProvider.search("ls").remove(0).item;
+            item.name = query.to_string();
+            vec![SearchResult {
+                item,
+                score: 0,
+                highlights: Vec::new(),
+            }]
+        }
+
+        fn activate(
+            &self,
+            _item: &AppInfo,
+            _settings: &LauncherSettings,
+        ) -> Result<(), Box<dyn std::error::Error>> {
+            Ok(())
+        }
+    }
+
+    fn slow(delay_ms: u64) -> Arc<SlowProvider> {
+        Arc::new(SlowProvider {
+            delay: Duration::from_millis(delay_ms),
+            calls: AtomicUsize::new(0),
+        })
+    }
+
+    fn names(batch: &SearchBatch) -> Vec<Vec<String>> {
+        batch
+            .results
+            .iter()
+            .map(|(_, results)| results.iter().map(|r| r.item.name.clone()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_results_come_back_in_provider_order() {
+        let (first, last) = (slow(1000), slow(1000));
+        let (worker, mut receiver) =
+            SearchWorker::new(vec![first.clone(), Arc::new(CommandProvider), last.clone()])
+                .unwrap();
+
+        let started = Instant::now();
+        let generation = worker.search("ls -l");
+        let batch = receiver.blocking_recv().unwrap();
+
+        assert_eq!(batch.generation, generation);
+        assert!(worker.is_current(generation));
+        assert_eq!(batch.query, "ls -l");
+        assert_eq!(
+            names(&batch),
+            vec![
+                vec!["ls -l".to_string()],
+                vec!["💻 Run: ls -l".to_string()],
+                vec!["ls -l".to_string()]
+            ]
+        );
+        assert_eq!(first.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(last.calls.load(Ordering::SeqCst), 1);
+        // The slow providers ran side by side; one after the other takes 2s
+        assert!(started.elapsed() < Duration::from_millis(1900));
+    }
+
+    #[test]
+    fn test_new_keystroke_cancels_stale_search() {
+        let provider = slow(200);
+        let (worker, mut receiver) = SearchWorker::new(vec![provider.clone()]).unwrap();
+
+        // Typed faster than the debounce: only the last query runs at all
+        worker.search("f");
+        worker.search("fi");
+        let last = worker.search("fir");
+        let batch = receiver.blocking_recv().unwrap();
+        assert_eq!(batch.generation, last);
+        assert_eq!(names(&batch), vec![vec!["fir".to_string()]]);
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+
+        // A search that is already running is abandoned when superseded
+        let stale = worker.search("fire");
+        std::thread::sleep(DEBOUNCE + Duration::from_millis(50));
+        let latest = worker.search("firef");
+        assert!(!worker.is_current(stale));
+        let batch = receiver.blocking_recv().unwrap();
+        assert_eq!(batch.generation, latest);
+        assert!(receiver.try_recv().is_err());
+    }
+}
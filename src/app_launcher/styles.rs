@@ -44,6 +44,12 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             background: transparent;
             border: none;
         }}
+
+        /* List view rows only carry the selection; the cards inside are styled */
+        .app-list > row {{
+            background: transparent;
+            padding: 0;
+        }}
         
         scrolledwindow {{
             background: transparent;
@@ -66,7 +72,7 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             box-shadow: 0 4px 8px rgba(0, 0, 0, 0.08);
         }}
         
-        .app-row:selected {{
+        row:selected .app-row {{
             background: {};
             border-color: {};
             color: #ffffff;
@@ -81,7 +87,7 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             margin-bottom: 2px;
         }}
         
-        .app-row:selected .app-name {{
+        row:selected .app-row .app-name {{
             color: #ffffff;
             font-weight: 600;
         }}
@@ -93,7 +99,7 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             line-height: 1.3;
         }}
         
-        .app-row:selected .app-description {{
+        row:selected .app-row .app-description {{
             color: rgba(255, 255, 255, 0.9);
         }}
         
@@ -113,6 +119,19 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             background: #2980b9;
         }}
         
+        /* Opens the desktop actions of an application */
+        .actions-button {{
+            background: transparent;
+            border: none;
+            border-radius: 6px;
+            padding: 6px;
+            min-width: 0;
+        }}
+
+        .actions-button:hover {{
+            background: rgba(0, 0, 0, 0.06);
+        }}
+
         /* Command rows with subtle green accent */
         .command-row {{
             background: #ffffff;
@@ -130,18 +149,18 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             box-shadow: 0 4px 8px rgba(40, 167, 69, 0.1);
         }}
         
-        .command-row:selected {{
+        row:selected .command-row {{
             background: #28a745;
             border-color: #28a745;
             color: #ffffff;
             box-shadow: 0 4px 12px rgba(40, 167, 69, 0.2);
         }}
         
-        .command-row:selected .app-name {{
+        row:selected .command-row .app-name {{
             color: #ffffff;
         }}
         
-        .command-row:selected .app-description {{
+        row:selected .command-row .app-description {{
             color: rgba(255, 255, 255, 0.9);
         }}
         
@@ -162,21 +181,39 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
             box-shadow: 0 4px 8px rgba(253, 126, 20, 0.1);
         }}
         
-        .file-row:selected {{
+        row:selected .file-row {{
             background: #fd7e14;
             border-color: #fd7e14;
             color: #ffffff;
             box-shadow: 0 4px 12px rgba(253, 126, 20, 0.2);
         }}
         
-        .file-row:selected .app-name {{
+        row:selected .file-row .app-name {{
             color: #ffffff;
         }}
         
-        .file-row:selected .app-description {{
+        row:selected .file-row .app-description {{
             color: rgba(255, 255, 255, 0.9);
         }}
         
+        /* Inline launch errors */
+        .error-row {{
+            background: #fdecea;
+            border-radius: 8px;
+            margin: 3px 8px;
+            padding: 12px 16px;
+            border: 1px solid #f5c6cb;
+            border-left: 4px solid #dc3545;
+        }}
+
+        .error-row .app-name {{
+            color: #721c24;
+        }}
+
+        .error-row .app-description {{
+            color: #a94442;
+        }}
+
         /* Clean section separators */
         .section-separator {{
             background: #e9ecef;
@@ -223,9 +260,9 @@ pub fn setup_styles(_window: &ApplicationWindow, settings: &LauncherSettings) {
         }}
         
         /* Ensure good contrast in all states */
-        .app-row:focus,
-        .command-row:focus,
-        .file-row:focus {{
+        row:focus .app-row,
+        row:focus .command-row,
+        row:focus .file-row {{
             outline: 2px solid {};
             outline-offset: 2px;
         }}
@@ -1,13 +1,14 @@
 // ui.rs - Debug version
 use gtk::prelude::*;
 use gtk::Box as GtkBox;
-use gtk::{ApplicationWindow, Entry, ListBox, ScrolledWindow};
+use gtk::{ApplicationWindow, Entry, ScrolledWindow};
+use super::result_list::ResultList;
 use crate::settings::LauncherSettings;
 
 pub fn setup_ui(
     window: &ApplicationWindow,
     search_entry: &mut Entry,
-    app_list: &mut ListBox,
+    results: &ResultList,
     _settings: &LauncherSettings,
 ) {
     // println!("Setting up UI, quit_on_close: {}", settings.behavior.quit_on_close);
@@ -31,12 +32,11 @@ pub fn setup_ui(
         .min_content_height(400)
         .build();
 
-    *app_list = ListBox::new();
-    app_list.set_selection_mode(gtk::SelectionMode::Single);
-    app_list.add_css_class("app-list");
-    scrolled.set_child(Some(app_list));
+    // The list view is scrollable itself, so only visible rows get widgets
+    scrolled.set_child(Some(results.view()));
 
     vbox.append(search_entry);
+    vbox.append(results.error_banner());
     vbox.append(&scrolled);
     window.set_child(Some(&vbox));
 
@@ -0,0 +1,168 @@
+// watcher.rs - Keep the providers up to date while the launcher keeps running
+//
+// Every path a provider watches gets a gio::FileMonitor (inotify on Linux).
+// Events are collected for a moment, then the provider refreshes itself on a
+// background thread. Afterwards the monitors follow the provider's new set of
+// watched paths, so directories created meanwhile are watched as well.
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::providers::{self, SearchProvider};
+
+// Copying or downloading produces bursts of events; refresh once per burst
+const SETTLE_DELAY: Duration = Duration::from_millis(500);
+
+pub struct ProviderWatcher {
+    _watches: Vec<Rc<Watch>>, // The monitors only hold weak references to these
+}
+
+struct Watch {
+    provider: Arc<dyn SearchProvider>,
+    monitors: RefCell<HashMap<PathBuf, gio::FileMonitor>>,
+    pending: RefCell<Vec<PathBuf>>, // Changed paths not handed to the provider yet
+    scheduled: Cell<bool>,
+    refreshing: Cell<bool>,
+    on_refreshed: Rc<dyn Fn()>,
+}
+
+impl ProviderWatcher {
+    /// Watch the paths of every provider and the providers they depend on.
+    /// `on_refreshed` runs on the main loop whenever a provider has new results.
+    pub fn new(providers: &[Arc<dyn SearchProvider>], on_refreshed: impl Fn() + 'static) -> Self {
+        let on_refreshed: Rc<dyn Fn()> = Rc::new(on_refreshed);
+        let watches = providers::watched_providers(providers)
+            .iter()
+            .map(|provider| {
+                let watch = Rc::new(Watch {
+                    provider: provider.clone(),
+                    monitors: RefCell::new(HashMap::new()),
+                    pending: RefCell::new(Vec::new()),
+                    scheduled: Cell::new(false),
+                    refreshing: Cell::new(false),
+                    on_refreshed: on_refreshed.clone(),
+                });
+                watch.update_monitors();
+                watch
+            })
+            .collect();
+
+        Self { _watches: watches }
+    }
+}
+
+impl Watch {
+    /// Monitor exactly the paths the provider currently wants watched
+    fn update_monitors(self: &Rc<Self>) {
+        let wanted: HashSet<PathBuf> = self.provider.watched_paths().into_iter().collect();
+        let mut monitors = self.monitors.borrow_mut();
+
+        monitors.retain(|path, monitor| {
+            let keep = wanted.contains(path);
+            if !keep {
+                monitor.cancel();
+            }
+            keep
+        });
+
+        for path in wanted {
+            if monitors.contains_key(&path) {
+                continue;
+            }
+            // Paths that do not exist yet are watched too; GIO notices when they appear
+            let monitor = gio::File::for_path(&path)
+                .monitor(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE);
+            match monitor {
+                Ok(monitor) => {
+                    let watch = Rc::downgrade(self);
+                    monitor.connect_changed(move |_, file, other_file, event| {
+                        if let Some(watch) = watch.upgrade() {
+                            watch.changed(file, other_file, event);
+                        }
+                    });
+                    monitors.insert(path, monitor);
+                }
+                Err(e) => eprintln!("Warning: Could not watch {}: {}", path.display(), e),
+            }
+        }
+        // println!("DEBUG: {} watching {} paths", self.provider.id(), monitors.len());
+    }
+
+    fn changed(
+        self: &Rc<Self>,
+        file: &gio::File,
+        other_file: Option<&gio::File>,
+        event: gio::FileMonitorEvent,
+    ) {
+        match event {
+            // Writes end with ChangesDoneHint; wait for that instead
+            gio::FileMonitorEvent::Changed
+            | gio::FileMonitorEvent::AttributeChanged
+            | gio::FileMonitorEvent::PreUnmount
+            | gio::FileMonitorEvent::Unmounted => return,
+            _ => {}
+        }
+        // println!("DEBUG: {:?} {:?}", event, file.path());
+
+        let mut pending = self.pending.borrow_mut();
+        pending.extend(file.path());
+        pending.extend(other_file.and_then(|file| file.path()));
+        drop(pending);
+        self.schedule();
+    }
+
+    fn schedule(self: &Rc<Self>) {
+        if self.scheduled.replace(true) {
+            return;
+        }
+        let watch = Rc::downgrade(self);
+        glib::timeout_add_local_once(SETTLE_DELAY, move || {
+            if let Some(watch) = watch.upgrade() {
+                watch.scheduled.set(false);
+                watch.refresh();
+            }
+        });
+    }
+
+    fn refresh(self: &Rc<Self>) {
+        // One refresh at a time; changes arriving meanwhile are handled after it
+        if self.refreshing.get() {
+            return;
+        }
+        let mut changed = std::mem::take(&mut *self.pending.borrow_mut());
+        if changed.is_empty() {
+            return;
+        }
+        changed.sort();
+        changed.dedup();
+        self.refreshing.set(true);
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let provider = self.provider.clone();
+        std::thread::spawn(move || {
+            provider.refresh(&changed);
+            let _ = sender.send(());
+        });
+
+        let watch = Rc::downgrade(self);
+        glib::MainContext::default().spawn_local(async move {
+            let _ = receiver.await;
+            let Some(watch) = watch.upgrade() else {
+                return;
+            };
+            watch.refreshing.set(false);
+            watch.update_monitors();
+            (watch.on_refreshed)();
+
+            if !watch.pending.borrow().is_empty() {
+                watch.schedule();
+            }
+        });
+    }
+}
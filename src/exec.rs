@@ -0,0 +1,338 @@
+// exec.rs - Exec key tokenizing and field code expansion
+//
+// Follows the "The Exec key" section of the Desktop Entry Specification.
+// Like GLib, single-quoted arguments and backslash escapes outside quotes are
+// also accepted since many real-world entries rely on them.
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::uri::file_uri;
+
+#[derive(Debug, PartialEq)]
+pub enum ExecError {
+    Empty,
+    UnterminatedQuote,
+    TrailingBackslash,
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::Empty => write!(f, "Exec line is empty"),
+            ExecError::UnterminatedQuote => write!(f, "Exec line has an unterminated quote"),
+            ExecError::TrailingBackslash => write!(f, "Exec line ends with a backslash"),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+/// Values substituted for field codes when launching an entry
+#[derive(Debug, Default)]
+pub struct LaunchContext<'a> {
+    pub files: &'a [PathBuf],
+    pub icon: Option<&'a str>,
+    pub name: &'a str,
+    pub desktop_file: Option<&'a Path>,
+}
+
+/// Split an Exec value into arguments, resolving quotes and escapes
+pub fn tokenize(exec: &str) -> Result<Vec<String>, ExecError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        // Inside double quotes only these characters may be escaped
+                        Some('\\') => match chars.next() {
+                            Some(e @ ('"' | '`' | '$' | '\\')) => current.push(e),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                            }
+                            None => return Err(ExecError::UnterminatedQuote),
+                        },
+                        Some(other) => current.push(other),
+                        None => return Err(ExecError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(other) => current.push(other),
+                        None => return Err(ExecError::UnterminatedQuote),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                match chars.next() {
+                    Some(escaped) => current.push(escaped),
+                    None => return Err(ExecError::TrailingBackslash),
+                }
+            }
+            _ => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+    if args.is_empty() {
+        return Err(ExecError::Empty);
+    }
+    Ok(args)
+}
+
+/// Quote a single argument so that `tokenize` returns it unchanged and no
+/// field codes are expanded in it
+pub fn quote_arg(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Tokenize `exec` and expand its field codes, returning one argument vector per
+/// process to start. Entries that only accept a single file (%f or %u) are
+/// launched once per file when several files are given.
+pub fn command_lines(exec: &str, context: &LaunchContext) -> Result<Vec<Vec<String>>, ExecError> {
+    let args = tokenize(exec)?;
+
+    let takes_list = args.iter().any(|arg| arg == "%F" || arg == "%U");
+    let takes_single = args
+        .iter()
+        .any(|arg| field_codes(arg).any(|code| code == 'f' || code == 'u'));
+
+    if takes_single && !takes_list && context.files.len() > 1 {
+        return Ok(context
+            .files
+            .iter()
+            .map(|file| expand_args(&args, context, std::slice::from_ref(file)))
+            .collect());
+    }
+
+    Ok(vec![expand_args(&args, context, context.files)])
+}
+
+fn expand_args(args: &[String], context: &LaunchContext, files: &[PathBuf]) -> Vec<String> {
+    let mut expanded = Vec::new();
+
+    for arg in args {
+        match arg.as_str() {
+            // List codes and %i are only valid as standalone arguments
+            "%F" => expanded.extend(files.iter().map(|f| f.to_string_lossy().into_owned())),
+            "%U" => expanded.extend(files.iter().map(|f| file_uri(f))),
+            "%i" => {
+                if let Some(icon) = context.icon.filter(|icon| !icon.is_empty()) {
+                    expanded.push("--icon".to_string());
+                    expanded.push(icon.to_string());
+                }
+            }
+            _ => {
+                if let Some(value) = expand_inline(arg, context, files) {
+                    expanded.push(value);
+                }
+            }
+        }
+    }
+
+    expanded
+}
+
+/// The field codes in `arg`, skipping escaped "%%"
+fn field_codes(arg: &str) -> impl Iterator<Item = char> + '_ {
+    let mut chars = arg.chars();
+    std::iter::from_fn(move || {
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+            match chars.next() {
+                Some('%') => {}
+                code => return code,
+            }
+        }
+        None
+    })
+}
+
+/// Expand the field codes embedded in a single argument. Returns None when the
+/// argument consisted only of a field code that expanded to nothing.
+fn expand_inline(arg: &str, context: &LaunchContext, files: &[PathBuf]) -> Option<String> {
+    let mut result = String::with_capacity(arg.len());
+    let mut had_code = false;
+    let mut chars = arg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => result.push('%'),
+            Some(code) => {
+                had_code = true;
+                match code {
+                    'f' | 'F' => {
+                        if let Some(file) = files.first() {
+                            result.push_str(&file.to_string_lossy());
+                        }
+                    }
+                    'u' | 'U' => {
+                        if let Some(file) = files.first() {
+                            result.push_str(&file_uri(file));
+                        }
+                    }
+                    'i' => result.push_str(context.icon.unwrap_or_default()),
+                    'c' => result.push_str(context.name),
+                    'k' => {
+                        if let Some(path) = context.desktop_file {
+                            result.push_str(&path.to_string_lossy());
+                        }
+                    }
+                    // Deprecated (%d, %D, %n, %N, %v, %m) and unknown codes are dropped
+                    _ => {}
+                }
+            }
+            None => result.push('%'),
+        }
+    }
+
+    if had_code && result.is_empty() {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_tokenize_quoting() {
+        assert_eq!(tokenize("firefox %u").unwrap(), strings(&["firefox", "%u"]));
+        assert_eq!(
+            tokenize(r#"sh -c "foo bar""#).unwrap(),
+            strings(&["sh", "-c", "foo bar"])
+        );
+        assert_eq!(
+            tokenize(r#""/opt/My App/app" --title "Say \"hi\" for \$5 \\o/""#).unwrap(),
+            strings(&["/opt/My App/app", "--title", r#"Say "hi" for $5 \o/"#])
+        );
+        assert_eq!(
+            tokenize("sh -c 'echo \"$HOME\"'").unwrap(),
+            strings(&["sh", "-c", "echo \"$HOME\""])
+        );
+        assert_eq!(tokenize(r"a\ b c").unwrap(), strings(&["a b", "c"]));
+        assert_eq!(tokenize(r#"empty """#).unwrap(), strings(&["empty", ""]));
+    }
+
+    #[test]
+    fn test_tokenize_errors() {
+        assert_eq!(tokenize("   "), Err(ExecError::Empty));
+        assert_eq!(tokenize(r#"sh -c "oops"#), Err(ExecError::UnterminatedQuote));
+        assert_eq!(tokenize("sh -c 'oops"), Err(ExecError::UnterminatedQuote));
+        assert_eq!(tokenize(r"trailing\"), Err(ExecError::TrailingBackslash));
+    }
+
+    #[test]
+    fn test_quote_arg_round_trips() {
+        let command = r#"echo "100%" `date` $HOME \n"#;
+        let exec = format!("sh -c {}", quote_arg(command));
+        let lines = command_lines(&exec, &LaunchContext::default()).unwrap();
+        assert_eq!(lines, vec![strings(&["sh", "-c", command])]);
+    }
+
+    #[test]
+    fn test_field_codes_without_files() {
+        let context = LaunchContext {
+            files: &[],
+            icon: Some("firefox"),
+            name: "Firefox",
+            desktop_file: Some(Path::new("/usr/share/applications/firefox.desktop")),
+        };
+        let lines = command_lines("firefox %u %i --class=%c %k 100%% %d", &context).unwrap();
+        assert_eq!(
+            lines,
+            vec![strings(&[
+                "firefox",
+                "--icon",
+                "firefox",
+                "--class=Firefox",
+                "/usr/share/applications/firefox.desktop",
+                "100%",
+            ])]
+        );
+    }
+
+    #[test]
+    fn test_field_codes_with_files() {
+        let files = vec![PathBuf::from("/tmp/a b.txt"), PathBuf::from("/tmp/c.txt")];
+        let context = LaunchContext {
+            files: &files,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            command_lines("gedit %F", &context).unwrap(),
+            vec![strings(&["gedit", "/tmp/a b.txt", "/tmp/c.txt"])]
+        );
+        assert_eq!(
+            command_lines("vlc %U", &context).unwrap(),
+            vec![strings(&["vlc", "file:///tmp/a%20b.txt", "file:///tmp/c.txt"])]
+        );
+        // Single-file codes start one process per file
+        assert_eq!(
+            command_lines("viewer --file=%f", &context).unwrap(),
+            vec![
+                strings(&["viewer", "--file=/tmp/a b.txt"]),
+                strings(&["viewer", "--file=/tmp/c.txt"]),
+            ]
+        );
+        // No icon means %i expands to nothing at all
+        assert_eq!(
+            command_lines("app %i %f", &context).unwrap().len(),
+            2
+        );
+        // An escaped %%f is literal text, not a single-file code
+        assert_eq!(
+            command_lines("printf 100%%f", &context).unwrap(),
+            vec![strings(&["printf", "100%f"])]
+        );
+    }
+}
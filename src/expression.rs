@@ -0,0 +1,454 @@
+// expression.rs - Arithmetic expressions for the calculator
+//
+// A recursive descent parser over a small token stream. Supports + - * / ^ (or **),
+// "mod", factorials, percentages, implicit multiplication ("2pi", "3(4+5)"),
+// hexadecimal, octal and binary literals, constants and common functions.
+// Percentages follow the usual calculator convention: "200 + 10%" is 220,
+// while "10%" on its own, or multiplied, is 0.1.
+use std::error::Error;
+use std::f64::consts;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char), // + - * / ^ % ! ( ) ,
+}
+
+/// The value of `expression`, which must be finite
+pub fn evaluate(expression: &str) -> Result<f64, Box<dyn Error>> {
+    let tokens = tokenize(expression)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let value = parser.expression()?.value;
+    if let Some(token) = parser.peek() {
+        return Err(format!("Unexpected {:?}", token).into());
+    }
+    if !value.is_finite() {
+        return Err("The result is not a finite number".into());
+    }
+    Ok(value)
+}
+
+/// A result the way a calculator shows it: whole numbers without a fraction, up to
+/// 12 significant digits, and scientific notation for very large or small values
+pub fn format_number(value: f64) -> String {
+    let magnitude = value.abs();
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    if !(1e-6..1e15).contains(&magnitude) {
+        let formatted = format!("{:.10e}", value);
+        let (mantissa, exponent) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+        return format!("{}e{}", trim_zeros(mantissa), exponent);
+    }
+    if value.fract() == 0.0 {
+        return format!("{}", value as i64);
+    }
+    let decimals = (11 - magnitude.log10().floor() as i32).clamp(0, 17) as usize;
+    trim_zeros(&format!("{:.*}", decimals, value)).to_string()
+}
+
+fn trim_zeros(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, Box<dyn Error>> {
+    let chars: Vec<char> = expression.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit()
+            || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let (number, end) = number(&chars, i)?;
+            tokens.push(Token::Number(number));
+            i = end;
+        } else if c.is_alphabetic() || c == '_' {
+            let end = i + chars[i..]
+                .iter()
+                .take_while(|c| c.is_alphanumeric() || **c == '_')
+                .count();
+            tokens.push(Token::Ident(
+                chars[i..end].iter().collect::<String>().to_lowercase(),
+            ));
+            i = end;
+        } else {
+            let op = match c {
+                '×' | '·' => '*',
+                '÷' => '/',
+                '−' => '-',
+                '*' if chars.get(i + 1) == Some(&'*') => {
+                    i += 1;
+                    '^'
+                }
+                '+' | '-' | '*' | '/' | '^' | '%' | '!' | '(' | ')' | ',' => c,
+                _ => return Err(format!("Unexpected character '{}'", c).into()),
+            };
+            tokens.push(Token::Op(op));
+            i += 1;
+        }
+    }
+    Ok(tokens)
+}
+
+/// A number literal starting at `start`: decimal with an optional exponent, or 0x/0o/0b
+fn number(chars: &[char], start: usize) -> Result<(f64, usize), Box<dyn Error>> {
+    let radix = match (chars[start], chars.get(start + 1)) {
+        ('0', Some('x' | 'X')) => Some(16),
+        ('0', Some('o' | 'O')) => Some(8),
+        ('0', Some('b' | 'B')) => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        let digits: String = chars[start + 2..]
+            .iter()
+            .take_while(|c| c.is_digit(radix) || **c == '_')
+            .collect();
+        let end = start + 2 + digits.chars().count();
+        let value = u64::from_str_radix(&digits.replace('_', ""), radix)
+            .map_err(|_| format!("Invalid base {} number", radix))?;
+        return Ok((value as f64, end));
+    }
+
+    let mut end = start;
+    let digits = |end: &mut usize| {
+        while chars
+            .get(*end)
+            .is_some_and(|c| c.is_ascii_digit() || *c == '_')
+        {
+            *end += 1;
+        }
+    };
+    digits(&mut end);
+    if chars.get(end) == Some(&'.') {
+        end += 1;
+        digits(&mut end);
+    }
+    // An exponent only if digits follow, so "2e" stays 2 times e
+    if matches!(chars.get(end), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(end + 1), Some('+' | '-')));
+        if chars.get(end + 1 + sign).is_some_and(char::is_ascii_digit) {
+            end += 1 + sign;
+            digits(&mut end);
+        }
+    }
+    let literal: String = chars[start..end].iter().filter(|c| **c != '_').collect();
+    let value = literal
+        .parse()
+        .map_err(|_| format!("Invalid number '{}'", literal))?;
+    Ok((value, end))
+}
+
+/// A value and whether it is a bare percentage, which addition treats specially
+#[derive(Debug, Clone, Copy)]
+struct Operand {
+    value: f64,
+    percent: bool,
+}
+
+impl From<f64> for Operand {
+    fn from(value: f64) -> Self {
+        Self {
+            value,
+            percent: false,
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, op: char) -> Result<(), Box<dyn Error>> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(format!("Expected '{}'", op).into())
+        }
+    }
+
+    /// Whether the next token is a "%" meaning modulo: one followed by an operand.
+    /// Otherwise it makes the value before it a percentage.
+    fn modulo_follows(&self) -> bool {
+        self.peek() == Some(&Token::Op('%'))
+            && matches!(
+                self.tokens.get(self.pos + 1),
+                Some(Token::Number(_) | Token::Ident(_) | Token::Op('('))
+            )
+    }
+
+    // expression := term (("+" | "-") term)*
+    fn expression(&mut self) -> Result<Operand, Box<dyn Error>> {
+        let mut left = self.term()?;
+        loop {
+            let sign = if self.eat('+') {
+                1.0
+            } else if self.eat('-') {
+                -1.0
+            } else {
+                return Ok(left);
+            };
+            let right = self.term()?;
+            let change = if right.percent {
+                left.value * right.value
+            } else {
+                right.value
+            };
+            left = (left.value + sign * change).into();
+        }
+    }
+
+    // term := unary (("*" | "/" | "%" | "mod" | implicit) unary)*
+    fn term(&mut self) -> Result<Operand, Box<dyn Error>> {
+        let mut left = self.unary()?;
+        loop {
+            if self.eat('*') {
+                left = (left.value * self.unary()?.value).into();
+            } else if self.eat('/') {
+                let divisor = self.unary()?.value;
+                if divisor == 0.0 {
+                    return Err("Division by zero".into());
+                }
+                left = (left.value / divisor).into();
+            } else if self.modulo_follows() || self.peek() == Some(&Token::Ident("mod".to_string()))
+            {
+                self.pos += 1;
+                left = left.value.rem_euclid(self.unary()?.value).into();
+            } else if matches!(self.peek(), Some(Token::Ident(_) | Token::Op('('))) {
+                // Implicit multiplication, e.g. "2pi" or "3(4 + 5)"
+                left = (left.value * self.power()?.value).into();
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    // unary := ("-" | "+") unary | power
+    fn unary(&mut self) -> Result<Operand, Box<dyn Error>> {
+        if self.eat('-') {
+            let operand = self.unary()?;
+            return Ok(Operand {
+                value: -operand.value,
+                ..operand
+            });
+        }
+        if self.eat('+') {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    // power := postfix ("^" unary)?, so 2^3^2 is 2^9 and -2^2 is -4
+    fn power(&mut self) -> Result<Operand, Box<dyn Error>> {
+        let base = self.postfix()?;
+        if self.eat('^') {
+            let exponent = self.unary()?;
+            return Ok(base.value.powf(exponent.value).into());
+        }
+        Ok(base)
+    }
+
+    // postfix := primary ("!" | "%")*, where "%" followed by an operand is modulo instead
+    fn postfix(&mut self) -> Result<Operand, Box<dyn Error>> {
+        let mut operand: Operand = self.primary()?.into();
+        loop {
+            if self.eat('!') {
+                operand = factorial(operand.value)?.into();
+            } else if self.peek() == Some(&Token::Op('%')) && !self.modulo_follows() {
+                self.pos += 1;
+                operand = Operand {
+                    value: operand.value / 100.0,
+                    percent: true,
+                };
+            } else {
+                return Ok(operand);
+            }
+        }
+    }
+
+    // primary := number | constant | function "(" arguments ")" | "(" expression ")"
+    fn primary(&mut self) -> Result<f64, Box<dyn Error>> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::Op('(')) => {
+                let value = self.expression()?.value;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(value) = constant(&name) {
+                    return Ok(value);
+                }
+                self.expect('(')?;
+                let mut arguments = vec![self.expression()?.value];
+                while self.eat(',') {
+                    arguments.push(self.expression()?.value);
+                }
+                self.expect(')')?;
+                call(&name, &arguments)
+            }
+            Some(token) => Err(format!("Unexpected {:?}", token).into()),
+            None => Err("Unexpected end of expression".into()),
+        }
+    }
+}
+
+fn constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" | "π" => Some(consts::PI),
+        "tau" | "τ" => Some(consts::TAU),
+        "e" => Some(consts::E),
+        "phi" | "φ" => Some((1.0 + 5f64.sqrt()) / 2.0),
+        _ => None,
+    }
+}
+
+fn call(name: &str, arguments: &[f64]) -> Result<f64, Box<dyn Error>> {
+    let one = |f: fn(f64) -> f64| match arguments {
+        [x] => Ok(f(*x)),
+        _ => Err(format!("{}() takes one argument", name)),
+    };
+    let value = match name {
+        "sqrt" => one(f64::sqrt),
+        "cbrt" => one(f64::cbrt),
+        "abs" => one(f64::abs),
+        "exp" => one(f64::exp),
+        "ln" => one(f64::ln),
+        "log" | "log10" => one(f64::log10),
+        "log2" => one(f64::log2),
+        "sin" => one(f64::sin),
+        "cos" => one(f64::cos),
+        "tan" => one(f64::tan),
+        "asin" => one(f64::asin),
+        "acos" => one(f64::acos),
+        "atan" => one(f64::atan),
+        "sinh" => one(f64::sinh),
+        "cosh" => one(f64::cosh),
+        "tanh" => one(f64::tanh),
+        "floor" => one(f64::floor),
+        "ceil" => one(f64::ceil),
+        "round" => one(f64::round),
+        "min" if !arguments.is_empty() => {
+            Ok(arguments.iter().copied().fold(f64::INFINITY, f64::min))
+        }
+        "max" if !arguments.is_empty() => {
+            Ok(arguments.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+        }
+        "pow" => match arguments {
+            [base, exponent] => Ok(base.powf(*exponent)),
+            _ => Err("pow() takes two arguments".to_string()),
+        },
+        _ => Err(format!("Unknown function '{}'", name)),
+    }?;
+    Ok(value)
+}
+
+fn factorial(n: f64) -> Result<f64, Box<dyn Error>> {
+    if n < 0.0 || n.fract() != 0.0 || n > 170.0 {
+        return Err("Factorials need a whole number from 0 to 170".into());
+    }
+    Ok((1..=n as u64).map(|k| k as f64).product())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str) -> f64 {
+        evaluate(expression).unwrap()
+    }
+
+    #[test]
+    fn test_precedence_and_associativity() {
+        assert_eq!(eval("2*(3+4)"), 14.0);
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("2^3^2"), 512.0);
+        assert_eq!(eval("2**10"), 1024.0);
+        assert_eq!(eval("-2^2"), -4.0);
+        assert_eq!(eval("(-2)^2"), 4.0);
+        assert_eq!(eval("7 mod 3"), 1.0);
+        assert_eq!(eval("7 % 3"), 1.0);
+        assert_eq!(eval("5!"), 120.0);
+        assert_eq!(eval("6 ÷ 4 × 2"), 3.0);
+    }
+
+    #[test]
+    fn test_functions_constants_and_literals() {
+        assert!((eval("sqrt(2)/3") - 0.471404520791).abs() < 1e-12);
+        assert_eq!(eval("2pi"), 2.0 * consts::PI);
+        assert_eq!(eval("3(4+5)"), 27.0);
+        assert_eq!(eval("max(1, 5, 3)"), 5.0);
+        assert_eq!(eval("log(1000)"), 3.0);
+        assert_eq!(eval("0xff + 0b101 + 0o10"), 268.0);
+        assert_eq!(eval("1.5e3"), 1500.0);
+        assert_eq!(eval("2e"), 2.0 * consts::E);
+        assert_eq!(eval("1_000 * 3"), 3000.0);
+    }
+
+    #[test]
+    fn test_percentages() {
+        assert_eq!(eval("50%"), 0.5);
+        assert_eq!(eval("200 + 10%"), 220.0);
+        assert_eq!(eval("200 - 25%"), 150.0);
+        assert_eq!(eval("20% * 50"), 10.0);
+        assert_eq!(eval("(1 + 1)%"), 0.02);
+        assert_eq!(eval("50% - 3"), -2.5);
+    }
+
+    #[test]
+    fn test_errors() {
+        for expression in [
+            "",
+            "2 +",
+            "(1",
+            "1 / 0",
+            "foo(2)",
+            "firefox",
+            "ls -la",
+            "3!!!!",
+            "sqrt(1, 2)",
+            "0xzz",
+        ] {
+            assert!(evaluate(expression).is_err(), "{} should fail", expression);
+        }
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(14.0), "14");
+        assert_eq!(format_number(0.1 + 0.2), "0.3");
+        assert_eq!(format_number(-2.5), "-2.5");
+        assert_eq!(format_number(1.0 / 3.0), "0.333333333333");
+        assert_eq!(format_number(2e20), "2e20");
+        assert_eq!(format_number(1.5e-9), "1.5e-9");
+    }
+}
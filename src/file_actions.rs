@@ -0,0 +1,147 @@
+// file_actions.rs - Things to do with a file result besides opening it
+use std::env;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::app_info::{AppInfo, ItemType};
+use crate::settings::LauncherSettings;
+use crate::spawner::spawn_detached;
+use crate::terminal::terminal_command;
+use crate::trash::move_to_trash;
+use crate::utils::{copy_files, copy_text, show_in_folder};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileAction {
+    ShowInFolder,
+    CopyPath,
+    CopyFile,
+    OpenTerminal,
+    MoveToTrash,
+}
+
+impl FileAction {
+    /// In the order they are offered
+    pub const ALL: [FileAction; 5] = [
+        FileAction::ShowInFolder,
+        FileAction::CopyPath,
+        FileAction::CopyFile,
+        FileAction::OpenTerminal,
+        FileAction::MoveToTrash,
+    ];
+
+    /// Stored in the item's payload to find the action again on activation
+    pub fn id(self) -> &'static str {
+        match self {
+            FileAction::ShowInFolder => "show-in-folder",
+            FileAction::CopyPath => "copy-path",
+            FileAction::CopyFile => "copy-file",
+            FileAction::OpenTerminal => "open-terminal",
+            FileAction::MoveToTrash => "move-to-trash",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.id() == id)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            FileAction::ShowInFolder => "Show in folder",
+            FileAction::CopyPath => "Copy path",
+            FileAction::CopyFile => "Copy file",
+            FileAction::OpenTerminal => "Open terminal here",
+            FileAction::MoveToTrash => "Move to trash",
+        }
+    }
+
+    fn icon(self) -> &'static str {
+        match self {
+            FileAction::ShowInFolder => "folder-open",
+            FileAction::CopyPath | FileAction::CopyFile => "edit-copy",
+            FileAction::OpenTerminal => "utilities-terminal",
+            FileAction::MoveToTrash => "user-trash",
+        }
+    }
+
+    /// The result item running this action on `file`
+    pub fn item(self, file: &AppInfo) -> AppInfo {
+        AppInfo {
+            desktop_id: None,
+            name: self.label().to_string(),
+            description: file
+                .file_path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_default(),
+            exec: String::new(),
+            icon: Some(self.icon().to_string()),
+            categories: Vec::new(),
+            keywords: Vec::new(),
+            item_type: ItemType::FileAction,
+            file_path: file.file_path.clone(),
+            source_path: None,
+            terminal: false,
+            working_dir: None,
+            actions: Vec::new(),
+            mime_types: Vec::new(),
+            mime_type: None,
+            payload: Some(self.id().to_string()),
+        }
+    }
+
+    pub fn run(self, path: &Path, settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+        // println!("DEBUG: {} {:?}", self.label(), path);
+        match self {
+            FileAction::ShowInFolder => show_in_folder(path),
+            FileAction::CopyPath => copy_text(&path.display().to_string()),
+            FileAction::CopyFile => copy_files(&[path.to_path_buf()]),
+            FileAction::OpenTerminal => open_terminal_in(&containing_dir(path), settings),
+            FileAction::MoveToTrash => move_to_trash(path).map(|_| ()),
+        }
+    }
+}
+
+/// The directory itself for a directory, otherwise the directory containing the file
+fn containing_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !path.is_dir() => parent.to_path_buf(),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Start the user's shell in a terminal emulator, in `dir`
+pub fn open_terminal_in(dir: &Path, settings: &LauncherSettings) -> Result<(), Box<dyn Error>> {
+    let mut argv = terminal_command(&settings.behavior.terminal)
+        .ok_or("No terminal emulator found; set behavior.terminal in settings.yaml")?;
+    // Running the shell explicitly works with every emulator's exec arguments
+    argv.push(
+        env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.is_empty())
+            .unwrap_or_else(|| "/bin/sh".to_string()),
+    );
+    spawn_detached(&argv, Some(dir))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_ids_round_trip() {
+        for action in FileAction::ALL {
+            assert_eq!(FileAction::from_id(action.id()), Some(action));
+        }
+        assert_eq!(FileAction::from_id("format-disk"), None);
+    }
+
+    #[test]
+    fn test_containing_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "").unwrap();
+        assert_eq!(containing_dir(&file), dir.path());
+        assert_eq!(containing_dir(dir.path()), dir.path());
+    }
+}
@@ -0,0 +1,235 @@
+// history.rs - Launch history and frecency ranking
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::xdg;
+
+const DAY: u64 = 24 * 60 * 60;
+
+// Scale from accumulated frecency to points added to a match score, and its cap,
+// so history reorders good matches without letting a poor match win outright
+const FRECENCY_DIVISOR: f64 = 4.0;
+const MAX_RANK_BONUS: f64 = 150.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub id: String,     // AppInfo::history_id of the launched item
+    pub query: String,  // What was typed when the item was picked
+    pub timestamp: u64, // Seconds since the Unix epoch
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LaunchHistory {
+    entries: Vec<HistoryEntry>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+    #[serde(skip)]
+    max_entries: usize,
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+impl LaunchHistory {
+    /// ~/.local/share/synaptrix/history.json (honouring XDG_DATA_HOME)
+    pub fn default_path() -> PathBuf {
+        xdg::data_home().join("synaptrix").join("history.json")
+    }
+
+    /// Load the history stored at `path`; a missing or unreadable file gives an empty history
+    pub fn load(path: &Path, max_entries: usize) -> Self {
+        let mut history = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<LaunchHistory>(&content).ok())
+            .unwrap_or_default();
+        history.path = Some(path.to_path_buf());
+        history.max_entries = max_entries;
+        history.trim();
+        history
+    }
+
+    pub fn entries(&self) -> &[HistoryEntry] {
+        &self.entries
+    }
+
+    /// Remember that `id` was launched after typing `query`, and persist the history
+    pub fn record(&mut self, id: &str, query: &str, timestamp: u64) {
+        self.entries.push(HistoryEntry {
+            id: id.to_string(),
+            query: query.trim().to_lowercase(),
+            timestamp,
+        });
+        self.trim();
+
+        if let Err(e) = self.save() {
+            eprintln!("Warning: Could not save launch history: {}", e);
+        }
+    }
+
+    /// Forget every launch, including the copy on disk
+    pub fn clear(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.entries.clear();
+        self.save()
+    }
+
+    fn trim(&mut self) {
+        if self.entries.len() > self.max_entries {
+            let excess = self.entries.len() - self.max_entries;
+            self.entries.drain(..excess);
+        }
+    }
+
+    fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Frecency of `id` for `query`: recent launches count more than old ones, and
+    /// launches picked after typing the same query count more than other launches
+    pub fn frecency(&self, id: &str, query: &str, now: u64) -> f64 {
+        let query = query.trim().to_lowercase();
+        self.entries
+            .iter()
+            .filter(|entry| entry.id == id)
+            .map(|entry| {
+                recency_weight(now.saturating_sub(entry.timestamp))
+                    * query_weight(&entry.query, &query)
+            })
+            .sum()
+    }
+
+    /// Points to add to the match score of `id`
+    pub fn rank_bonus(&self, id: &str, query: &str, now: u64) -> i64 {
+        (self.frecency(id, query, now) / FRECENCY_DIVISOR).min(MAX_RANK_BONUS) as i64
+    }
+}
+
+fn recency_weight(age: u64) -> f64 {
+    match age / DAY {
+        0..=3 => 100.0,
+        4..=13 => 70.0,
+        14..=30 => 50.0,
+        31..=89 => 30.0,
+        _ => 10.0,
+    }
+}
+
+fn query_weight(recorded: &str, query: &str) -> f64 {
+    if recorded == query {
+        1.0
+    } else if !query.is_empty() && (recorded.starts_with(query) || query.starts_with(recorded)) {
+        0.5
+    } else {
+        0.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const NOW: u64 = 1_750_000_000;
+
+    fn in_memory(max_entries: usize) -> LaunchHistory {
+        LaunchHistory {
+            max_entries,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_same_query_outranks_other_queries() {
+        let mut history = in_memory(100);
+        for day in 0..5 {
+            history.record("app:code.desktop", "co", NOW - day * DAY);
+        }
+        history.record("app:gnome-control-center.desktop", "control", NOW);
+
+        assert!(
+            history.rank_bonus("app:code.desktop", "co", NOW)
+                > history.rank_bonus("app:gnome-control-center.desktop", "co", NOW)
+        );
+        // Typing further along the remembered query still helps
+        assert!(history.frecency("app:code.desktop", "c", NOW) > 0.0);
+        assert_eq!(history.rank_bonus("app:unknown.desktop", "co", NOW), 0);
+    }
+
+    #[test]
+    fn test_recent_launches_weigh_more() {
+        let mut history = in_memory(100);
+        history.record("recent", "", NOW - DAY);
+        history.record("old", "", NOW - 200 * DAY);
+        assert!(history.frecency("recent", "", NOW) > history.frecency("old", "", NOW));
+    }
+
+    #[test]
+    fn test_rank_bonus_is_capped() {
+        let mut history = in_memory(1000);
+        for _ in 0..500 {
+            history.record("app:firefox.desktop", "fire", NOW);
+        }
+        assert_eq!(
+            history.rank_bonus("app:firefox.desktop", "fire", NOW),
+            MAX_RANK_BONUS as i64
+        );
+    }
+
+    #[test]
+    fn test_cap_drops_oldest_entries() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        let mut history = LaunchHistory::load(&path, 3);
+        for i in 0..5 {
+            history.record(&format!("item-{}", i), "", NOW + i);
+        }
+        let ids: Vec<_> = history.entries().iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["item-2", "item-3", "item-4"]);
+
+        // A smaller cap also applies to history loaded from disk
+        let reloaded = LaunchHistory::load(&path, 2);
+        assert_eq!(reloaded.entries().len(), 2);
+    }
+
+    #[test]
+    fn test_persist_and_clear() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join("history.json");
+
+        let mut history = LaunchHistory::load(&path, 10);
+        history.record("app:firefox.desktop", "  FF ", NOW);
+
+        let mut reloaded = LaunchHistory::load(&path, 10);
+        assert_eq!(
+            reloaded.entries(),
+            &[HistoryEntry {
+                id: "app:firefox.desktop".to_string(),
+                query: "ff".to_string(),
+                timestamp: NOW,
+            }]
+        );
+
+        reloaded.clear().unwrap();
+        assert!(LaunchHistory::load(&path, 10).entries().is_empty());
+    }
+
+    #[test]
+    fn test_corrupt_file_gives_empty_history() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("history.json");
+        fs::write(&path, "not json").unwrap();
+        assert!(LaunchHistory::load(&path, 10).entries().is_empty());
+    }
+}
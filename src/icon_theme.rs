@@ -0,0 +1,449 @@
+// icon_theme.rs - Icon lookup as described by the FreeDesktop.org Icon Theme spec
+//
+// Searches the selected theme, the themes it inherits from and then hicolor in
+// every icon base directory, picking the directory whose size suits the request
+// best, and finally the unthemed icons in pixmaps. Directory listings and
+// results are cached, so showing many rows does not stat the same paths over
+// and over; a resolver is replaced rather than updated when themes change.
+// See https://specifications.freedesktop.org/icon-theme-spec/latest/
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::app_launcher::desktop_entry::DesktopEntry;
+use crate::xdg;
+
+const INDEX_GROUP: &str = "Icon Theme";
+const FALLBACK_THEME: &str = "hicolor";
+// In order of preference
+const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+/// Where themes and unthemed icons are looked up, most important first:
+/// ~/.icons, icons/ in every data directory, and pixmaps
+pub fn base_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![xdg::home_dir().join(".icons")];
+    dirs.extend(xdg::data_search_path().iter().map(|dir| dir.join("icons")));
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    dirs
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SizeType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// A subdirectory of a theme and the sizes its icons are for
+#[derive(Debug, Clone)]
+struct ThemeDir {
+    path: String,
+    size: u32,
+    scale: u32,
+    size_type: SizeType,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+#[derive(Debug, Clone)]
+struct Theme {
+    name: String,
+    dirs: Vec<ThemeDir>,
+}
+
+pub struct IconResolver {
+    base_dirs: Vec<PathBuf>,
+    themes: Vec<Theme>, // In search order
+    listings: Mutex<HashMap<PathBuf, HashSet<String>>>,
+    found: Mutex<HashMap<(String, u32, u32), Option<PathBuf>>>,
+}
+
+impl IconResolver {
+    /// A resolver for the theme called `theme` in the standard base directories
+    pub fn new(theme: &str) -> Self {
+        Self::with_base_dirs(theme, base_dirs())
+    }
+
+    pub fn with_base_dirs(theme: &str, base_dirs: Vec<PathBuf>) -> Self {
+        let themes = theme_chain(theme, &base_dirs);
+        // println!("DEBUG: Icon themes: {:?}", themes.iter().map(|t| &t.name).collect::<Vec<_>>());
+        Self {
+            base_dirs,
+            themes,
+            listings: Mutex::new(HashMap::new()),
+            found: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The file of the icon called `name` best suited for `size` pixels at `scale`.
+    /// Absolute paths, as some desktop entries use, are returned if they exist.
+    pub fn lookup(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        if name.is_empty() {
+            return None;
+        }
+        if Path::new(name).is_absolute() {
+            return Path::new(name).is_file().then(|| PathBuf::from(name));
+        }
+        // Icon= should not have an extension, but often does
+        let name = EXTENSIONS
+            .iter()
+            .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+            .unwrap_or(name);
+
+        let key = (name.to_string(), size, scale);
+        if let Some(found) = self
+            .found
+            .lock()
+            .ok()
+            .and_then(|found| found.get(&key).cloned())
+        {
+            return found;
+        }
+        let found = self
+            .themes
+            .iter()
+            .find_map(|theme| self.lookup_in_theme(theme, name, size, scale))
+            .or_else(|| self.lookup_unthemed(name));
+        if let Ok(mut cache) = self.found.lock() {
+            cache.insert(key, found.clone());
+        }
+        found
+    }
+
+    /// An icon in a directory matching the size exactly, or else the closest one
+    fn lookup_in_theme(&self, theme: &Theme, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let mut closest: Option<(u32, PathBuf)> = None;
+        for dir in &theme.dirs {
+            let matches = dir.matches_size(size, scale);
+            let distance = dir.size_distance(size, scale);
+            if !matches && closest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                continue;
+            }
+            for base in &self.base_dirs {
+                let Some(file) = self.find_file(&base.join(&theme.name).join(&dir.path), name)
+                else {
+                    continue;
+                };
+                if matches {
+                    return Some(file);
+                }
+                closest = Some((distance, file));
+                break;
+            }
+        }
+        closest.map(|(_, file)| file)
+    }
+
+    /// Icons directly in a base directory, e.g. /usr/share/pixmaps/foo.png
+    fn lookup_unthemed(&self, name: &str) -> Option<PathBuf> {
+        self.base_dirs
+            .iter()
+            .find_map(|base| self.find_file(base, name))
+    }
+
+    /// `name` with one of the icon extensions in `dir`, from a cached listing
+    fn find_file(&self, dir: &Path, name: &str) -> Option<PathBuf> {
+        let mut listings = self.listings.lock().ok()?;
+        let listing = listings.entry(dir.to_path_buf()).or_insert_with(|| {
+            fs::read_dir(dir)
+                .map(|entries| {
+                    entries
+                        .flatten()
+                        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                        .collect()
+                })
+                .unwrap_or_default()
+        });
+        EXTENSIONS
+            .iter()
+            .map(|ext| format!("{}.{}", name, ext))
+            .find(|file| listing.contains(file))
+            .map(|file| dir.join(file))
+    }
+}
+
+/// The selected theme and the themes it inherits from, depth first, with hicolor last
+fn theme_chain(theme: &str, base_dirs: &[PathBuf]) -> Vec<Theme> {
+    fn visit(
+        name: &str,
+        base_dirs: &[PathBuf],
+        themes: &mut Vec<Theme>,
+        seen: &mut HashSet<String>,
+    ) {
+        if name == FALLBACK_THEME || !seen.insert(name.to_string()) {
+            return;
+        }
+        let Some(index) = load_index(name, base_dirs) else {
+            return;
+        };
+        let parents = list(index.raw(INDEX_GROUP, "Inherits").unwrap_or_default());
+        themes.push(Theme {
+            name: name.to_string(),
+            dirs: theme_dirs(&index),
+        });
+        for parent in parents {
+            visit(&parent, base_dirs, themes, seen);
+        }
+    }
+
+    let mut themes = Vec::new();
+    visit(theme, base_dirs, &mut themes, &mut HashSet::new());
+    if let Some(index) = load_index(FALLBACK_THEME, base_dirs) {
+        themes.push(Theme {
+            name: FALLBACK_THEME.to_string(),
+            dirs: theme_dirs(&index),
+        });
+    }
+    themes
+}
+
+/// The index.theme of `name` in the first base directory that has one
+fn load_index(name: &str, base_dirs: &[PathBuf]) -> Option<DesktopEntry> {
+    base_dirs
+        .iter()
+        .find_map(|base| fs::read_to_string(base.join(name).join("index.theme")).ok())
+        .map(|content| DesktopEntry::parse(&content))
+}
+
+fn theme_dirs(index: &DesktopEntry) -> Vec<ThemeDir> {
+    let mut paths = list(index.raw(INDEX_GROUP, "Directories").unwrap_or_default());
+    paths.extend(list(
+        index
+            .raw(INDEX_GROUP, "ScaledDirectories")
+            .unwrap_or_default(),
+    ));
+    let mut seen = HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+
+    paths
+        .into_iter()
+        .filter_map(|path| {
+            let number = |key: &str| {
+                index
+                    .raw(&path, key)
+                    .and_then(|value| value.trim().parse().ok())
+            };
+            let size = number("Size")?;
+            let size_type = match index.raw(&path, "Type").map(str::trim) {
+                Some("Fixed") => SizeType::Fixed,
+                Some("Scalable") => SizeType::Scalable,
+                _ => SizeType::Threshold,
+            };
+            Some(ThemeDir {
+                size,
+                scale: number("Scale").unwrap_or(1),
+                size_type,
+                min_size: number("MinSize").unwrap_or(size),
+                max_size: number("MaxSize").unwrap_or(size),
+                threshold: number("Threshold").unwrap_or(2),
+                path,
+            })
+        })
+        .collect()
+}
+
+/// index.theme lists are separated by commas
+fn list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+impl ThemeDir {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.size_type {
+            SizeType::Fixed => self.size == size,
+            SizeType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            SizeType::Threshold => (self.size.saturating_sub(self.threshold)
+                ..=self.size + self.threshold)
+                .contains(&size),
+        }
+    }
+
+    /// How far the icons of this directory are from the wanted size, in device pixels
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let wanted = size * scale;
+        let (min, max) = match self.size_type {
+            SizeType::Fixed => (self.size, self.size),
+            SizeType::Scalable => (self.min_size, self.max_size),
+            SizeType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+        let (min, max) = (min * self.scale, max * self.scale);
+        if wanted < min {
+            min - wanted
+        } else {
+            wanted.saturating_sub(max)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn write(path: PathBuf, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn index(inherits: &str, dirs: &[(&str, &str)]) -> String {
+        let mut index = format!(
+            "[Icon Theme]\nName=Test\nInherits={}\nDirectories={}\n",
+            inherits,
+            dirs.iter()
+                .map(|(dir, _)| *dir)
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        for (dir, keys) in dirs {
+            index.push_str(&format!("\n[{}]\n{}\n", dir, keys));
+        }
+        index
+    }
+
+    /// A user base dir with the "Custom" theme, and a system one with its parent and hicolor
+    fn base_dirs() -> (tempfile::TempDir, Vec<PathBuf>) {
+        let root = tempdir().unwrap();
+        let user = root.path().join("user");
+        let system = root.path().join("system");
+        let pixmaps = root.path().join("pixmaps");
+
+        write(
+            user.join("Custom/index.theme"),
+            &index(
+                "Parent",
+                &[
+                    ("16x16/apps", "Size=16\nType=Fixed"),
+                    ("48x48/apps", "Size=48\nType=Fixed"),
+                    ("48x48@2/apps", "Size=48\nScale=2\nType=Fixed"),
+                ],
+            ),
+        );
+        write(user.join("Custom/16x16/apps/small.png"), "");
+        write(user.join("Custom/48x48/apps/editor.svg"), "");
+        write(user.join("Custom/48x48@2/apps/editor.png"), "");
+        // Subdirectories of a theme may be spread over several base directories
+        write(system.join("Custom/48x48/apps/spread.png"), "");
+
+        write(
+            system.join("Parent/index.theme"),
+            &index(
+                "hicolor",
+                &[(
+                    "scalable/apps",
+                    "Size=64\nType=Scalable\nMinSize=8\nMaxSize=512",
+                )],
+            ),
+        );
+        write(system.join("Parent/scalable/apps/browser.svg"), "");
+        write(system.join("Parent/scalable/apps/small.svg"), "");
+
+        write(
+            system.join("hicolor/index.theme"),
+            &index("", &[("32x32/apps", "Size=32")]),
+        );
+        write(system.join("hicolor/32x32/apps/player.png"), "");
+        write(pixmaps.join("legacy.xpm"), "");
+
+        (root, vec![user, system, pixmaps])
+    }
+
+    #[test]
+    fn test_lookup_follows_themes_and_sizes() {
+        let (_root, dirs) = base_dirs();
+        let resolver = IconResolver::with_base_dirs("Custom", dirs.clone());
+        let lookup = |name: &str, size: u32, scale: u32| resolver.lookup(name, size, scale);
+
+        assert_eq!(
+            lookup("editor", 48, 1),
+            Some(dirs[0].join("Custom/48x48/apps/editor.svg"))
+        );
+        assert_eq!(
+            lookup("editor", 48, 2),
+            Some(dirs[0].join("Custom/48x48@2/apps/editor.png"))
+        );
+        assert_eq!(
+            lookup("spread", 48, 1),
+            Some(dirs[1].join("Custom/48x48/apps/spread.png"))
+        );
+        // The selected theme's closest size beats an exact match in a parent theme
+        assert_eq!(
+            lookup("small", 48, 1),
+            Some(dirs[0].join("Custom/16x16/apps/small.png"))
+        );
+        assert_eq!(
+            lookup("browser", 48, 1),
+            Some(dirs[1].join("Parent/scalable/apps/browser.svg"))
+        );
+        // Threshold directories accept sizes close to theirs; others are the closest match
+        assert_eq!(
+            lookup("player", 48, 1),
+            Some(dirs[1].join("hicolor/32x32/apps/player.png"))
+        );
+        assert_eq!(lookup("legacy", 48, 1), Some(dirs[2].join("legacy.xpm")));
+        assert_eq!(
+            lookup("legacy.xpm", 48, 1),
+            Some(dirs[2].join("legacy.xpm"))
+        );
+        assert_eq!(lookup("missing", 48, 1), None);
+    }
+
+    #[test]
+    fn test_absolute_paths_and_caching() {
+        let (root, dirs) = base_dirs();
+        let resolver = IconResolver::with_base_dirs("Custom", dirs.clone());
+        let absolute = dirs[2].join("legacy.xpm");
+        assert_eq!(
+            resolver.lookup(&absolute.to_string_lossy(), 48, 1),
+            Some(absolute)
+        );
+        assert_eq!(resolver.lookup("/does/not/exist.png", 48, 1), None);
+
+        let found = resolver.lookup("editor", 48, 1);
+        assert!(found.is_some());
+        // Results are remembered rather than looked up again
+        fs::remove_dir_all(root.path().join("user")).unwrap();
+        assert_eq!(resolver.lookup("editor", 48, 1), found);
+    }
+
+    #[test]
+    fn test_unknown_theme_falls_back_to_hicolor() {
+        let (_root, dirs) = base_dirs();
+        let resolver = IconResolver::with_base_dirs("Missing", dirs.clone());
+        assert_eq!(resolver.lookup("editor", 48, 1), None);
+        assert_eq!(
+            resolver.lookup("player", 24, 1),
+            Some(dirs[1].join("hicolor/32x32/apps/player.png"))
+        );
+    }
+
+    #[test]
+    fn test_size_distance() {
+        let dir = ThemeDir {
+            path: "scalable".to_string(),
+            size: 48,
+            scale: 1,
+            size_type: SizeType::Scalable,
+            min_size: 16,
+            max_size: 256,
+            threshold: 2,
+        };
+        assert!(dir.matches_size(100, 1));
+        assert!(!dir.matches_size(100, 2));
+        assert_eq!(dir.size_distance(100, 1), 0);
+        assert_eq!(dir.size_distance(8, 1), 8);
+        assert_eq!(dir.size_distance(200, 2), 144);
+    }
+}
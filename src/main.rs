@@ -6,10 +6,26 @@ use std::cell::RefCell;
 
 mod app_info;
 mod app_launcher;
+mod exec;
+mod expression;
+mod file_actions;
+mod history;
+mod icon_theme;
+mod mime;
 mod settings;
+mod spawner;
+mod terminal;
+mod thumbnails;
+mod trash;
+mod units;
+mod uri;
 mod utils;
+mod xbel;
+mod xdg;
+mod xml;
 
 use app_launcher::AppLauncher;
+use history::LaunchHistory;
 use settings::LauncherSettings;
 
 fn main() {
@@ -49,13 +65,29 @@ fn main() {
 
     // Handle command line for both primary and remote instances
     let launcher_clone2 = launcher.clone();
-    app.connect_command_line(move |app, _cmdline| {
+    app.connect_command_line(move |app, cmdline| {
         // Check if we're the primary instance
         if app.is_remote() {
             // We're a remote instance trying to communicate with primary
             return 0;
         }
 
+        // `synaptrix --clear-history` forgets all launches without showing the window
+        if cmdline.arguments().iter().any(|arg| arg == "--clear-history") {
+            let launcher_ref = launcher_clone2.try_borrow().ok();
+            match launcher_ref.as_ref().and_then(|launcher| launcher.as_ref()) {
+                // A running launcher also drops the history it holds in memory
+                Some(launcher) => launcher.clear_history(),
+                None => {
+                    if let Err(e) = LaunchHistory::load(&LaunchHistory::default_path(), 0).clear() {
+                        eprintln!("Warning: Could not clear launch history: {}", e);
+                    }
+                }
+            }
+            println!("Launch history cleared");
+            return 0;
+        }
+
         // We're the primary instance, handle the command
         if let Ok(launcher_ref) = launcher_clone2.try_borrow() {
             if let Some(ref launcher) = *launcher_ref {
@@ -0,0 +1,668 @@
+// mime.rs - MIME type detection with the shared-mime-info database
+//
+// Reads the files update-mime-database generates in every mime/ data directory:
+// globs2 (file name patterns), magic (content rules), aliases, subclasses and
+// the icon tables. Names decide when they are conclusive; otherwise the first
+// bytes of the file are sniffed. See
+// https://specifications.freedesktop.org/shared-mime-info-spec/latest/
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::app_launcher::file_index::glob_match;
+use crate::xdg;
+
+const MAGIC_HEADER: &[u8] = b"MIME-Magic\0\n";
+// Never read more of a file than this for sniffing
+const MAX_SNIFF_BYTES: usize = 16 * 1024;
+
+pub const DIRECTORY: &str = "inode/directory";
+pub const EMPTY: &str = "application/x-zerosize";
+pub const TEXT: &str = "text/plain";
+pub const BINARY: &str = "application/octet-stream";
+
+/// The database of the current session, loaded on first use
+pub fn database() -> &'static MimeDatabase {
+    static DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+    DATABASE.get_or_init(MimeDatabase::load)
+}
+
+#[derive(Debug, Default)]
+pub struct MimeDatabase {
+    globs: Vec<Glob>,         // Highest weight first
+    magic: Vec<MagicSection>, // Highest priority first
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+    sniff_length: usize, // How much of a file the magic rules look at
+}
+
+#[derive(Debug)]
+struct Glob {
+    weight: u32,
+    mime_type: String,
+    pattern: String, // Lowercased unless case-sensitive
+    case_sensitive: bool,
+    literal: bool,
+}
+
+#[derive(Debug)]
+struct MagicSection {
+    priority: u32,
+    mime_type: String,
+    rules: Vec<MagicRule>,
+}
+
+/// One line of a magic section; rules indented below a rule must match as well
+#[derive(Debug)]
+struct MagicRule {
+    indent: u32,
+    offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    range: usize, // Number of offsets, starting at `offset`, to try
+}
+
+impl MimeDatabase {
+    /// The database in the mime/ directories of the XDG data directories
+    pub fn load() -> Self {
+        Self::from_dirs(&xdg::data_search_path())
+    }
+
+    /// Merge the mime/ directories of `data_dirs`, most important first
+    pub fn from_dirs(data_dirs: &[PathBuf]) -> Self {
+        let mut database = Self::default();
+        // Types whose globs or magic a more important directory replaced
+        let mut no_globs = HashSet::new();
+        let mut no_magic = HashSet::new();
+
+        for dir in data_dirs.iter().map(|dir| dir.join("mime")) {
+            let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap_or_default();
+
+            let globs = parse_globs2(&read("globs2"));
+            let cleared: Vec<String> = globs
+                .iter()
+                .filter(|glob| glob.pattern.eq_ignore_ascii_case("__NOGLOBS__"))
+                .map(|glob| glob.mime_type.clone())
+                .collect();
+            database.globs.extend(globs.into_iter().filter(|glob| {
+                !glob.pattern.eq_ignore_ascii_case("__NOGLOBS__")
+                    && !no_globs.contains(&glob.mime_type)
+            }));
+            no_globs.extend(cleared);
+
+            let (magic, cleared) = fs::read(dir.join("magic"))
+                .ok()
+                .and_then(|data| parse_magic(&data))
+                .unwrap_or_default();
+            database.magic.extend(
+                magic
+                    .into_iter()
+                    .filter(|section| !no_magic.contains(&section.mime_type)),
+            );
+            no_magic.extend(cleared);
+
+            for (alias, canonical) in pairs(&read("aliases"), ' ') {
+                database.aliases.entry(alias).or_insert(canonical);
+            }
+            for (child, parent) in pairs(&read("subclasses"), ' ') {
+                let parents = database.parents.entry(child).or_default();
+                if !parents.contains(&parent) {
+                    parents.push(parent);
+                }
+            }
+            for (mime_type, icon) in pairs(&read("icons"), ':') {
+                database.icons.entry(mime_type).or_insert(icon);
+            }
+            for (mime_type, icon) in pairs(&read("generic-icons"), ':') {
+                database.generic_icons.entry(mime_type).or_insert(icon);
+            }
+        }
+
+        // Stable sorts keep the directory order among equals
+        database
+            .globs
+            .sort_by_key(|glob| std::cmp::Reverse(glob.weight));
+        database
+            .magic
+            .sort_by_key(|section| std::cmp::Reverse(section.priority));
+        database.sniff_length = database
+            .magic
+            .iter()
+            .flat_map(|section| &section.rules)
+            .map(|rule| rule.offset + rule.range + rule.value.len())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_SNIFF_BYTES);
+        database
+    }
+
+    /// The MIME type of the file at `path`, from its name, or from its contents
+    /// when the name does not decide
+    pub fn detect(&self, path: &Path) -> String {
+        if path.is_dir() {
+            return DIRECTORY.to_string();
+        }
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let by_name = self.types_for_name(&name);
+        if let [mime_type] = by_name.as_slice() {
+            return mime_type.clone();
+        }
+
+        let head = read_head(path, self.sniff_length);
+        if let Some(sniffed) = head.as_deref().and_then(|data| self.sniff(data)) {
+            // A name match of the sniffed type or a subtype of it is the more precise one
+            if let Some(mime_type) = by_name.iter().find(|t| self.is_subclass(t, sniffed)) {
+                return mime_type.clone();
+            }
+            if by_name.is_empty() {
+                return sniffed.to_string();
+            }
+        }
+        if !by_name.is_empty() {
+            // Without magic to decide, text contents at least rule out binary types
+            let is_text = head.as_deref().is_some_and(looks_like_text);
+            return by_name
+                .iter()
+                .find(|t| is_text && self.is_subclass(t, TEXT))
+                .unwrap_or(&by_name[0])
+                .clone();
+        }
+        match head {
+            Some(data) if data.is_empty() => EMPTY.to_string(),
+            Some(data) if looks_like_text(&data) => TEXT.to_string(),
+            _ => BINARY.to_string(),
+        }
+    }
+
+    /// The types whose best glob matches `name` equally well: literal names first,
+    /// then the highest weight, then the longest pattern
+    pub fn types_for_name(&self, name: &str) -> Vec<String> {
+        let lowercase = name.to_lowercase();
+        let matches: Vec<&Glob> = self
+            .globs
+            .iter()
+            .filter(|glob| {
+                let name = if glob.case_sensitive {
+                    name
+                } else {
+                    &lowercase
+                };
+                if glob.literal {
+                    glob.pattern == name
+                } else {
+                    glob_match(&glob.pattern, name)
+                }
+            })
+            .collect();
+
+        let literals: Vec<&Glob> = matches.iter().copied().filter(|g| g.literal).collect();
+        let candidates = if literals.is_empty() {
+            matches
+        } else {
+            literals
+        };
+        let Some(best) = candidates
+            .iter()
+            .map(|glob| (glob.weight, glob.pattern.len()))
+            .max()
+        else {
+            return Vec::new();
+        };
+
+        let mut types: Vec<String> = Vec::new();
+        for glob in candidates {
+            let mime_type = self.canonical(&glob.mime_type);
+            if (glob.weight, glob.pattern.len()) == best && !types.iter().any(|t| t == mime_type) {
+                types.push(mime_type.to_string());
+            }
+        }
+        types
+    }
+
+    /// The type of the highest priority magic section matching `data`
+    pub fn sniff(&self, data: &[u8]) -> Option<&str> {
+        self.magic
+            .iter()
+            .find(|section| rules_match(&section.rules, data))
+            .map(|section| self.canonical(&section.mime_type))
+    }
+
+    /// The canonical name of a type that may be an alias, e.g. application/pdf
+    /// for application/x-pdf
+    pub fn canonical<'a>(&'a self, mime_type: &'a str) -> &'a str {
+        self.aliases
+            .get(mime_type)
+            .map(String::as_str)
+            .unwrap_or(mime_type)
+    }
+
+    /// `mime_type` followed by the types it is a kind of, nearest first. Every text
+    /// type is a kind of text/plain.
+    pub fn ancestors(&self, mime_type: &str) -> Vec<String> {
+        let mut ancestors = vec![self.canonical(mime_type).to_string()];
+        let mut i = 0;
+        while i < ancestors.len() {
+            let mut parents: Vec<String> =
+                self.parents.get(&ancestors[i]).cloned().unwrap_or_default();
+            if ancestors[i].starts_with("text/") && ancestors[i] != TEXT {
+                parents.push(TEXT.to_string());
+            }
+            for parent in parents {
+                let parent = self.canonical(&parent).to_string();
+                if !ancestors.contains(&parent) {
+                    ancestors.push(parent);
+                }
+            }
+            i += 1;
+        }
+        ancestors
+    }
+
+    pub fn is_subclass(&self, mime_type: &str, parent: &str) -> bool {
+        self.ancestors(mime_type)
+            .iter()
+            .any(|ancestor| ancestor == self.canonical(parent))
+    }
+
+    /// Icon names for `mime_type`, most specific first: its entry in the icons
+    /// table, the type with '/' replaced by '-', then its generic icon
+    pub fn icon_names(&self, mime_type: &str) -> Vec<String> {
+        let mime_type = self.canonical(mime_type);
+        let mut names = Vec::new();
+        names.extend(self.icons.get(mime_type).cloned());
+        names.push(mime_type.replace('/', "-"));
+        match self.generic_icons.get(mime_type) {
+            Some(generic) => names.push(generic.clone()),
+            None => {
+                let media = mime_type.split('/').next().unwrap_or_default();
+                names.push(format!("{}-x-generic", media));
+            }
+        }
+        names.dedup();
+        names
+    }
+}
+
+/// Lines of the form "<weight>:<type>:<pattern>[:<flags>]"
+fn parse_globs2(content: &str) -> Vec<Glob> {
+    content
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, ':');
+            let weight = fields.next()?.parse().ok()?;
+            let mime_type = fields.next()?.to_string();
+            let pattern = fields.next()?;
+            let case_sensitive = fields
+                .next()
+                .is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+            Some(Glob {
+                weight,
+                mime_type,
+                pattern: if case_sensitive {
+                    pattern.to_string()
+                } else {
+                    pattern.to_lowercase()
+                },
+                case_sensitive,
+                literal: !pattern.contains(['*', '?', '[']),
+            })
+        })
+        .collect()
+}
+
+/// The sections of a magic file, and the types whose magic from less important
+/// directories is to be ignored. A section that cannot be parsed is skipped.
+fn parse_magic(data: &[u8]) -> Option<(Vec<MagicSection>, Vec<String>)> {
+    let mut rest = data.strip_prefix(MAGIC_HEADER)?;
+    let mut sections = Vec::new();
+    let mut cleared = Vec::new();
+
+    while !rest.is_empty() {
+        match parse_magic_section(rest) {
+            Some((section, no_magic, after)) => {
+                if no_magic {
+                    cleared.push(section.mime_type.clone());
+                }
+                sections.push(section);
+                rest = after;
+            }
+            None => {
+                // println!("DEBUG: Skipping a malformed magic section");
+                rest = match rest[1..].windows(2).position(|w| w == b"\n[") {
+                    Some(i) => &rest[i + 2..],
+                    None => &[],
+                };
+            }
+        }
+    }
+    Some((sections, cleared))
+}
+
+/// "[<priority>:<type>]" and its rules, whether it clears the type's magic, and the rest
+fn parse_magic_section(data: &[u8]) -> Option<(MagicSection, bool, &[u8])> {
+    let after = data.strip_prefix(b"[")?;
+    let end = after.iter().position(|b| *b == b'\n')?;
+    let header = std::str::from_utf8(&after[..end]).ok()?.strip_suffix(']')?;
+    let (priority, mime_type) = header.split_once(':')?;
+    let mut section = MagicSection {
+        priority: priority.parse().ok()?,
+        mime_type: mime_type.to_string(),
+        rules: Vec::new(),
+    };
+    let mut no_magic = false;
+
+    let mut rest = &after[end + 1..];
+    while !rest.is_empty() && !rest.starts_with(b"[") {
+        if let Some(after) = rest.strip_prefix(b">__NOMAGIC__\n") {
+            no_magic = true;
+            rest = after;
+            continue;
+        }
+        let (rule, after) = parse_magic_rule(rest)?;
+        section.rules.push(rule);
+        rest = after;
+    }
+    Some((section, no_magic, rest))
+}
+
+/// [<indent>]><offset>=<length><value>[&<mask>][~<word size>][+<range>]\n
+fn parse_magic_rule(data: &[u8]) -> Option<(MagicRule, &[u8])> {
+    let (indent, rest) = parse_number(data).unwrap_or((0, data));
+    let rest = rest.strip_prefix(b">")?;
+    let (offset, rest) = parse_number(rest)?;
+    let rest = rest.strip_prefix(b"=")?;
+    let length = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+    let mut value = rest.get(2..2 + length)?.to_vec();
+    let mut rest = &rest[2 + length..];
+
+    let mut mask = None;
+    if let Some(after) = rest.strip_prefix(b"&") {
+        mask = Some(after.get(..length)?.to_vec());
+        rest = &after[length..];
+    }
+    let mut word_size = 1;
+    if let Some(after) = rest.strip_prefix(b"~") {
+        (word_size, rest) = parse_number(after)?;
+    }
+    let mut range = 1;
+    if let Some(after) = rest.strip_prefix(b"+") {
+        (range, rest) = parse_number(after)?;
+    }
+    // Anything else up to the end of the line is reserved for extensions
+    let end = rest.iter().position(|b| *b == b'\n')?;
+    rest = &rest[end + 1..];
+
+    // Multi-byte words are stored big-endian
+    if word_size > 1 && cfg!(target_endian = "little") {
+        for bytes in std::iter::once(&mut value).chain(mask.as_mut()) {
+            for word in bytes.chunks_mut(word_size) {
+                word.reverse();
+            }
+        }
+    }
+
+    Some((
+        MagicRule {
+            indent: indent as u32,
+            offset,
+            value,
+            mask,
+            range: range.max(1),
+        },
+        rest,
+    ))
+}
+
+fn parse_number(data: &[u8]) -> Option<(usize, &[u8])> {
+    let digits = data.iter().take_while(|b| b.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&data[..digits]).ok()?.parse().ok()?;
+    Some((number, &data[digits..]))
+}
+
+/// Whether any rule at the top level matches along with one of its nested rules, if it has any
+fn rules_match(rules: &[MagicRule], data: &[u8]) -> bool {
+    let mut i = 0;
+    while i < rules.len() {
+        let rule = &rules[i];
+        let nested = rules[i + 1..]
+            .iter()
+            .take_while(|nested| nested.indent > rule.indent)
+            .count();
+        let children = &rules[i + 1..i + 1 + nested];
+        if rule.matches(data) && (children.is_empty() || rules_match(children, data)) {
+            return true;
+        }
+        i += 1 + nested;
+    }
+    false
+}
+
+impl MagicRule {
+    fn matches(&self, data: &[u8]) -> bool {
+        (self.offset..self.offset + self.range).any(|start| {
+            let Some(window) = data.get(start..start + self.value.len()) else {
+                return false;
+            };
+            match &self.mask {
+                Some(mask) => window
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((byte, value), mask)| byte & mask == value & mask),
+                None => window == self.value.as_slice(),
+            }
+        })
+    }
+}
+
+/// "a b" or "a:b" pairs, one per line
+fn pairs(content: &str, separator: char) -> Vec<(String, String)> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once(separator))
+        .map(|(a, b)| (a.trim().to_string(), b.trim().to_string()))
+        .collect()
+}
+
+fn read_head(path: &Path, length: usize) -> Option<Vec<u8>> {
+    let file = fs::File::open(path).ok()?;
+    let mut head = Vec::with_capacity(length);
+    file.take(length.max(1) as u64)
+        .read_to_end(&mut head)
+        .ok()?;
+    Some(head)
+}
+
+/// UTF-8 without control characters other than whitespace, allowing a character
+/// cut off at the end
+fn looks_like_text(data: &[u8]) -> bool {
+    let valid = match std::str::from_utf8(data) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    valid
+        && data
+            .iter()
+            .all(|b| *b >= 0x20 && *b != 0x7f || matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn magic_rule(line: &[u8], value: &[u8]) -> Vec<u8> {
+        let mut rule = line.to_vec();
+        rule.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        rule.extend_from_slice(value);
+        rule
+    }
+
+    fn write_database(dir: &Path) {
+        let mime = dir.join("mime");
+        fs::create_dir_all(&mime).unwrap();
+        fs::write(
+            mime.join("globs2"),
+            "# comment\n\
+             50:text/x-rust:*.rs\n\
+             50:application/x-ole-storage:*.doc\n\
+             50:text/x-doc:*.doc\n\
+             50:application/x-tar:*.tar\n\
+             50:application/x-compressed-tar:*.tar.gz\n\
+             50:application/gzip:*.gz\n\
+             50:text/x-makefile:makefile\n\
+             10:text/x-readme:readme*\n\
+             50:image/x-custom:*.IMG:cs\n",
+        )
+        .unwrap();
+
+        let mut magic = MAGIC_HEADER.to_vec();
+        magic.extend_from_slice(b"[60:application/pdf]\n");
+        magic.extend(magic_rule(b">0=", b"%PDF-"));
+        magic.extend_from_slice(b"\n");
+        // A section with a malformed rule is skipped, but not the ones after it
+        magic.extend_from_slice(b"[55:application/x-broken]\n>zero=\n");
+        magic.extend_from_slice(b"[50:application/x-ole-storage]\n");
+        magic.extend(magic_rule(b">0=", b"\xd0\xcf\x11\xe0"));
+        magic.extend_from_slice(b"\n");
+        // PNG signature, then an IHDR chunk somewhere in the first 16 bytes
+        magic.extend_from_slice(b"[50:image/png]\n");
+        magic.extend(magic_rule(b">0=", b"\x89PNG"));
+        magic.extend_from_slice(b"\n");
+        magic.extend(magic_rule(b"1>8=", b"IHDR"));
+        magic.extend_from_slice(b"+8\n");
+        // A masked match: the low bit of the third byte is ignored
+        magic.extend_from_slice(b"[40:application/x-masked]\n");
+        magic.extend(magic_rule(b">0=", b"MK\x02"));
+        magic.extend_from_slice(b"&\xff\xff\xfe\n");
+        fs::write(mime.join("magic"), magic).unwrap();
+
+        fs::write(mime.join("aliases"), "application/x-pdf application/pdf\n").unwrap();
+        fs::write(
+            mime.join("subclasses"),
+            "application/x-compressed-tar application/gzip\ntext/x-doc text/plain\n",
+        )
+        .unwrap();
+        fs::write(mime.join("icons"), "application/pdf:adobe-pdf\n").unwrap();
+        fs::write(
+            mime.join("generic-icons"),
+            "application/x-compressed-tar:package-x-generic\n",
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_detection_by_name() {
+        let dir = tempdir().unwrap();
+        write_database(dir.path());
+        let database = MimeDatabase::from_dirs(&[dir.path().to_path_buf()]);
+
+        assert_eq!(database.types_for_name("main.RS"), vec!["text/x-rust"]);
+        // The longest pattern wins
+        assert_eq!(
+            database.types_for_name("backup.tar.gz"),
+            vec!["application/x-compressed-tar"]
+        );
+        // Literal names beat patterns, whatever their weight
+        assert_eq!(database.types_for_name("Makefile"), vec!["text/x-makefile"]);
+        assert_eq!(database.types_for_name("README.md"), vec!["text/x-readme"]);
+        // Case-sensitive patterns
+        assert_eq!(database.types_for_name("disk.IMG"), vec!["image/x-custom"]);
+        assert!(database.types_for_name("disk.img").is_empty());
+        assert_eq!(database.types_for_name("letter.doc").len(), 2);
+    }
+
+    #[test]
+    fn test_detection_by_content() {
+        let dir = tempdir().unwrap();
+        write_database(dir.path());
+        let database = MimeDatabase::from_dirs(&[dir.path().to_path_buf()]);
+        let files = dir.path().join("files");
+        fs::create_dir_all(&files).unwrap();
+        let detect = |name: &str, content: &[u8]| {
+            let path = files.join(name);
+            fs::write(&path, content).unwrap();
+            database.detect(&path)
+        };
+
+        // The IHDR rule looks furthest: 8 offsets from 8, 4 bytes long
+        assert_eq!(database.sniff_length, 20);
+        assert!(database
+            .magic
+            .iter()
+            .all(|section| section.mime_type != "application/x-broken"));
+        assert_eq!(detect("paper", b"%PDF-1.7 ..."), "application/pdf");
+        assert_eq!(
+            detect("picture", b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR"),
+            "image/png"
+        );
+        // The nested rule must match too
+        assert_eq!(detect("broken", b"\x89PNG\r\n\x1a\n\0\0\0\x0dIDAT"), BINARY);
+        assert_eq!(detect("masked", b"MK\x03"), "application/x-masked");
+        // Ambiguous names are settled by the contents
+        assert_eq!(
+            detect("word.doc", b"\xd0\xcf\x11\xe0..."),
+            "application/x-ole-storage"
+        );
+        assert_eq!(detect("notes.doc", b"plain words"), "text/x-doc");
+        assert_eq!(detect("notes", b"plain words\n"), TEXT);
+        assert_eq!(detect("blob", b"\x00\x01\x02"), BINARY);
+        assert_eq!(detect("empty", b""), EMPTY);
+        assert_eq!(database.detect(&files), DIRECTORY);
+    }
+
+    #[test]
+    fn test_hierarchy_and_icons() {
+        let dir = tempdir().unwrap();
+        write_database(dir.path());
+        let database = MimeDatabase::from_dirs(&[dir.path().to_path_buf()]);
+
+        assert_eq!(database.canonical("application/x-pdf"), "application/pdf");
+        assert_eq!(
+            database.ancestors("application/x-compressed-tar"),
+            vec!["application/x-compressed-tar", "application/gzip"]
+        );
+        assert_eq!(database.ancestors("text/x-rust"), vec!["text/x-rust", TEXT]);
+        assert!(database.is_subclass("text/x-doc", "text/plain"));
+
+        assert_eq!(
+            database.icon_names("application/x-pdf"),
+            vec!["adobe-pdf", "application-pdf", "application-x-generic"]
+        );
+        assert_eq!(
+            database.icon_names("application/x-compressed-tar"),
+            vec!["application-x-compressed-tar", "package-x-generic"]
+        );
+        assert_eq!(
+            database.icon_names("text/x-rust"),
+            vec!["text-x-rust", "text-x-generic"]
+        );
+    }
+
+    #[test]
+    fn test_more_important_directories_replace_globs() {
+        let user = tempdir().unwrap();
+        let system = tempdir().unwrap();
+        write_database(system.path());
+        fs::create_dir_all(user.path().join("mime")).unwrap();
+        fs::write(
+            user.path().join("mime/globs2"),
+            "50:text/x-rust:__NOGLOBS__\n50:text/x-rust:*.rust\n",
+        )
+        .unwrap();
+
+        let database =
+            MimeDatabase::from_dirs(&[user.path().to_path_buf(), system.path().to_path_buf()]);
+        assert_eq!(database.types_for_name("lib.rust"), vec!["text/x-rust"]);
+        assert!(database.types_for_name("lib.rs").is_empty());
+    }
+}
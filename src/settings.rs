@@ -8,6 +8,10 @@ pub struct LauncherSettings {
     pub theme: ThemeSettings,
     pub behavior: BehaviorSettings,
     pub recent_files: RecentFilesSettings,
+    #[serde(default)]
+    pub history: HistorySettings,
+    #[serde(default = "ProviderSettings::defaults")]
+    pub providers: Vec<ProviderSettings>, // Result sources, listed in display order
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +34,10 @@ pub struct BehaviorSettings {
     pub auto_close: bool,
     pub show_descriptions: bool,
     pub quit_on_close: bool, // New setting: true = quit app, false = stay in memory
+    #[serde(default)]
+    pub terminal: String, // Terminal for Terminal=true apps; empty = auto-detect
+    #[serde(default)]
+    pub show_all_desktops: bool, // Ignore OnlyShowIn/NotShowIn desktop restrictions
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +46,77 @@ pub struct RecentFilesSettings {
     pub max_files: usize,
     pub directories: Vec<String>,
     pub xbel_path: String,
+    #[serde(default = "RecentFilesSettings::default_max_depth")]
+    pub max_depth: usize, // Subdirectory levels to index below each directory; 0 = top level only
+    #[serde(default)]
+    pub show_hidden: bool, // Index dot files and dot directories
+    #[serde(default = "RecentFilesSettings::default_use_ignore_files")]
+    pub use_ignore_files: bool, // Honour .gitignore and .hidden files in indexed directories
+    #[serde(default = "RecentFilesSettings::default_exclude")]
+    pub exclude: Vec<String>, // Glob patterns, in .gitignore syntax, never indexed
+    #[serde(default = "RecentFilesSettings::default_record_opened")]
+    pub record_opened: bool, // Add files opened from the launcher to the XBEL file
+}
+
+impl RecentFilesSettings {
+    fn default_max_depth() -> usize {
+        5
+    }
+
+    fn default_use_ignore_files() -> bool {
+        true
+    }
+
+    fn default_record_opened() -> bool {
+        true
+    }
+
+    fn default_exclude() -> Vec<String> {
+        ["node_modules/", "__pycache__/", "*~", "*.swp"]
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySettings {
+    pub enabled: bool, // Remember launches and rank frequently picked items higher
+    pub max_entries: usize, // Oldest launches are forgotten beyond this
+}
+
+impl Default for HistorySettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_entries: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderSettings {
+    pub name: String, // "applications", "recent_files", "commands", "calculator", "conversions" or "plugins"
+    pub enabled: bool,
+}
+
+impl ProviderSettings {
+    fn defaults() -> Vec<Self> {
+        [
+            "calculator",
+            "conversions",
+            "commands",
+            "applications",
+            "recent_files",
+            "plugins",
+        ]
+        .iter()
+        .map(|name| Self {
+            name: name.to_string(),
+            enabled: true,
+        })
+        .collect()
+    }
 }
 
 impl Default for LauncherSettings {
@@ -58,6 +137,8 @@ impl Default for LauncherSettings {
                 auto_close: true,
                 show_descriptions: true,
                 quit_on_close: false, // Default to staying in memory (daemon mode)
+                terminal: String::new(),
+                show_all_desktops: false,
             },
             recent_files: RecentFilesSettings {
                 enabled: true,
@@ -69,7 +150,14 @@ impl Default for LauncherSettings {
                     "~/Desktop".to_string(),
                     "~/Pictures".to_string(),
                 ],
+                max_depth: RecentFilesSettings::default_max_depth(),
+                show_hidden: false,
+                use_ignore_files: RecentFilesSettings::default_use_ignore_files(),
+                exclude: RecentFilesSettings::default_exclude(),
+                record_opened: RecentFilesSettings::default_record_opened(),
             },
+            history: HistorySettings::default(),
+            providers: ProviderSettings::defaults(),
         }
     }
 }
@@ -235,6 +323,50 @@ mod tests {
         assert_eq!(settings.behavior.quit_on_close, false); // Default is daemon mode
     }
 
+    #[test]
+    fn test_missing_new_fields_use_defaults() {
+        // Settings files written by older versions must keep loading
+        let mut value = serde_yaml::to_value(LauncherSettings::default()).unwrap();
+        value["behavior"]
+            .as_mapping_mut()
+            .unwrap()
+            .remove("terminal");
+        value["behavior"]
+            .as_mapping_mut()
+            .unwrap()
+            .remove("show_all_desktops");
+        value.as_mapping_mut().unwrap().remove("history");
+        value.as_mapping_mut().unwrap().remove("providers");
+        let recent_files = value["recent_files"].as_mapping_mut().unwrap();
+        for key in ["max_depth", "show_hidden", "use_ignore_files", "exclude", "record_opened"] {
+            recent_files.remove(key);
+        }
+
+        let yaml = serde_yaml::to_string(&value).unwrap();
+        let loaded: LauncherSettings = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(loaded.behavior.terminal, "");
+        assert!(!loaded.behavior.show_all_desktops);
+        assert!(loaded.history.enabled);
+        assert_eq!(loaded.history.max_entries, 1000);
+        let providers: Vec<_> = loaded.providers.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(
+            providers,
+            vec![
+                "calculator",
+                "conversions",
+                "commands",
+                "applications",
+                "recent_files",
+                "plugins",
+            ]
+        );
+        assert_eq!(loaded.recent_files.max_depth, 5);
+        assert!(!loaded.recent_files.show_hidden);
+        assert!(loaded.recent_files.use_ignore_files);
+        assert!(loaded.recent_files.exclude.contains(&"node_modules/".to_string()));
+        assert!(loaded.recent_files.record_opened);
+    }
+
     #[test]
     fn test_path_expansion() {
         std::env::set_var("HOME", "/home/testuser");
@@ -0,0 +1,226 @@
+// spawner.rs - Start launched programs detached from the launcher
+//
+// Children are started in a new session and double-forked, so they are
+// reparented to init (or the session's subreaper) instead of the launcher.
+// The intermediate process exits immediately and is reaped right away, which
+// keeps the daemon free of zombies however long the launched program runs.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug)]
+pub struct SpawnError {
+    pub program: String,
+    pub source: io::Error,
+}
+
+impl fmt::Display for SpawnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Failed to start {}: {}", self.program, self.source)
+    }
+}
+
+impl std::error::Error for SpawnError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Start `argv[0]` with the remaining arguments, detached from the launcher,
+/// optionally in `working_dir`. Errors such as a missing or non-executable
+/// binary are returned rather than aborting, since the launcher may be a
+/// long-running daemon.
+pub fn spawn_detached(argv: &[String], working_dir: Option<&Path>) -> Result<(), SpawnError> {
+    let Some((program, args)) = argv.split_first() else {
+        return Err(SpawnError {
+            program: String::new(),
+            source: io::Error::new(io::ErrorKind::InvalidInput, "empty command line"),
+        });
+    };
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).stdin(Stdio::null());
+    if let Some(dir) = working_dir {
+        cmd.current_dir(dir);
+    }
+
+    // SAFETY: only async-signal-safe functions are called between fork and exec
+    unsafe {
+        cmd.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            match libc::fork() {
+                -1 => Err(io::Error::last_os_error()),
+                // The grandchild continues on to exec the program
+                0 => Ok(()),
+                // The intermediate child exits so the grandchild is orphaned
+                _ => libc::_exit(0),
+            }
+        });
+    }
+
+    // Exec failures in the grandchild are still reported here, because it holds
+    // the close-on-exec error pipe that spawn() waits on
+    let mut child = cmd.spawn().map_err(|source| SpawnError {
+        program: program.clone(),
+        source,
+    })?;
+
+    // Reap the intermediate child, which exits right after forking
+    child.wait().map_err(|source| SpawnError {
+        program: program.clone(),
+        source,
+    })?;
+
+    Ok(())
+}
+
+/// Locate an executable by absolute path or by searching $PATH
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable(&path).then_some(path);
+    }
+
+    let path_var = std::env::var_os("PATH")?;
+    std::env::split_paths(&path_var)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+fn is_executable(path: &Path) -> bool {
+    fs::metadata(path)
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+    use tempfile::tempdir;
+
+    fn write_script(path: &Path, body: &str, mode: u32) {
+        fs::write(path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn wait_for_file(path: &Path) -> Option<String> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Ok(content) = fs::read_to_string(path) {
+                if !content.is_empty() {
+                    return Some(content);
+                }
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        None
+    }
+
+    #[test]
+    fn test_spawns_fake_executable_with_arguments() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("fake-app");
+        let marker = dir.path().join("marker");
+        write_script(&script, "echo \"$1 $2\" > \"$3.tmp\" && mv \"$3.tmp\" \"$3\"", 0o755);
+
+        spawn_detached(&[
+            script.to_string_lossy().into_owned(),
+            "hello".to_string(),
+            "world".to_string(),
+            marker.to_string_lossy().into_owned(),
+        ], None)
+        .unwrap();
+
+        assert_eq!(wait_for_file(&marker).as_deref(), Some("hello world\n"));
+    }
+
+    #[test]
+    fn test_child_runs_in_new_session() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("fake-app");
+        let marker = dir.path().join("marker");
+        // Field 6 of /proc/self/stat is the session ID
+        write_script(
+            &script,
+            "cut -d' ' -f6 /proc/$$/stat > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"",
+            0o755,
+        );
+
+        spawn_detached(&[
+            script.to_string_lossy().into_owned(),
+            marker.to_string_lossy().into_owned(),
+        ], None)
+        .unwrap();
+
+        let session: i32 = wait_for_file(&marker).unwrap().trim().parse().unwrap();
+        // SAFETY: getsid(0) only queries the calling process and touches no memory of ours
+        assert_ne!(session, unsafe { libc::getsid(0) });
+    }
+
+    #[test]
+    fn test_missing_executable_is_an_error() {
+        let err = spawn_detached(&["/nonexistent/synaptrix-test-binary".to_string()], None)
+            .unwrap_err();
+        assert_eq!(err.source.kind(), io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("synaptrix-test-binary"));
+    }
+
+    #[test]
+    fn test_non_executable_file_is_an_error() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("not-executable");
+        write_script(&script, "true", 0o644);
+
+        let err = spawn_detached(&[script.to_string_lossy().into_owned()], None).unwrap_err();
+        assert_eq!(err.source.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn test_empty_command_line_is_an_error() {
+        assert!(spawn_detached(&[], None).is_err());
+    }
+
+    #[test]
+    fn test_working_directory() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("fake-app");
+        let marker = dir.path().join("marker");
+        write_script(&script, "pwd > \"$1.tmp\" && mv \"$1.tmp\" \"$1\"", 0o755);
+
+        let work_dir = dir.path().join("work");
+        fs::create_dir(&work_dir).unwrap();
+        spawn_detached(
+            &[script.to_string_lossy().into_owned(), marker.to_string_lossy().into_owned()],
+            Some(&work_dir),
+        )
+        .unwrap();
+
+        let pwd = wait_for_file(&marker).unwrap();
+        assert_eq!(Path::new(pwd.trim()).canonicalize().unwrap(), work_dir.canonicalize().unwrap());
+
+        // A missing working directory is reported instead of silently ignored
+        let missing = dir.path().join("missing");
+        assert!(spawn_detached(&[script.to_string_lossy().into_owned()], Some(&missing)).is_err());
+    }
+
+    #[test]
+    fn test_find_executable() {
+        let dir = tempdir().unwrap();
+        let script = dir.path().join("fake-app");
+        write_script(&script, "true", 0o755);
+        let plain = dir.path().join("plain-file");
+        write_script(&plain, "true", 0o644);
+
+        assert_eq!(find_executable(&script.to_string_lossy()), Some(script.clone()));
+        assert!(find_executable(&plain.to_string_lossy()).is_none());
+        assert!(find_executable("sh").is_some());
+        assert!(find_executable("synaptrix-no-such-binary").is_none());
+    }
+}
@@ -0,0 +1,128 @@
+// terminal.rs - Terminal emulator selection for Terminal=true entries
+use crate::exec;
+use crate::spawner::find_executable;
+
+/// Known terminal emulators in auto-detection order, with the arguments that
+/// make them run the command line that follows
+const KNOWN_TERMINALS: &[(&str, &[&str])] = &[
+    ("x-terminal-emulator", &["-e"]),
+    ("gnome-terminal", &["--"]),
+    ("konsole", &["-e"]),
+    ("xfce4-terminal", &["-x"]),
+    ("mate-terminal", &["-x"]),
+    ("tilix", &["-e"]),
+    ("terminator", &["-x"]),
+    ("kitty", &[]),
+    ("alacritty", &["-e"]),
+    ("wezterm", &["start", "--"]),
+    ("foot", &[]),
+    ("lxterminal", &["-e"]),
+    ("urxvt", &["-e"]),
+    ("xterm", &["-e"]),
+];
+
+/// The argument prefix to run a command inside a terminal emulator.
+///
+/// `configured` is the `behavior.terminal` setting: empty means auto-detect,
+/// a bare known terminal name gets its usual exec flag appended, and anything
+/// else (e.g. "alacritty --class launcher -e") is used verbatim.
+pub fn terminal_command(configured: &str) -> Option<Vec<String>> {
+    resolve(
+        configured,
+        std::env::var("TERMINAL").ok().as_deref(),
+        |program| find_executable(program).is_some(),
+    )
+}
+
+fn resolve(
+    configured: &str,
+    env_terminal: Option<&str>,
+    is_available: impl Fn(&str) -> bool,
+) -> Option<Vec<String>> {
+    if !configured.trim().is_empty() {
+        return command_for(configured);
+    }
+
+    // $TERMINAL is a common convention for the user's preferred emulator
+    if let Some(terminal) = env_terminal.filter(|t| !t.trim().is_empty()) {
+        if let Some(command) = command_for(terminal) {
+            if is_available(&command[0]) {
+                return Some(command);
+            }
+        }
+    }
+
+    KNOWN_TERMINALS
+        .iter()
+        .find(|(program, _)| is_available(program))
+        .map(|(program, args)| with_exec_args(program, args))
+}
+
+fn command_for(value: &str) -> Option<Vec<String>> {
+    let args = exec::tokenize(value).ok()?;
+    if args.len() > 1 {
+        return Some(args);
+    }
+
+    let program = &args[0];
+    let name = program.rsplit('/').next().unwrap_or(program);
+    let exec_args = KNOWN_TERMINALS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, exec_args)| *exec_args)
+        // Most emulators accept -e
+        .unwrap_or(&["-e"]);
+    Some(with_exec_args(program, exec_args))
+}
+
+fn with_exec_args(program: &str, args: &[&str]) -> Vec<String> {
+    std::iter::once(program)
+        .chain(args.iter().copied())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_configured_terminal() {
+        let none = |_: &str| false;
+        assert_eq!(resolve("kitty", None, none), Some(strings(&["kitty"])));
+        assert_eq!(
+            resolve("/usr/bin/gnome-terminal", None, none),
+            Some(strings(&["/usr/bin/gnome-terminal", "--"]))
+        );
+        assert_eq!(
+            resolve("alacritty --class launcher -e", None, none),
+            Some(strings(&["alacritty", "--class", "launcher", "-e"]))
+        );
+        assert_eq!(resolve("my-term", None, none), Some(strings(&["my-term", "-e"])));
+    }
+
+    #[test]
+    fn test_auto_detection() {
+        let only = |available: &'static [&'static str]| {
+            move |program: &str| available.contains(&program)
+        };
+        assert_eq!(
+            resolve("", None, only(&["xterm", "konsole"])),
+            Some(strings(&["konsole", "-e"]))
+        );
+        assert_eq!(
+            resolve("", Some("foot"), only(&["xterm", "foot"])),
+            Some(strings(&["foot"]))
+        );
+        // An unavailable $TERMINAL falls through to detection
+        assert_eq!(
+            resolve("", Some("foot"), only(&["xterm"])),
+            Some(strings(&["xterm", "-e"]))
+        );
+        assert_eq!(resolve("", None, only(&[])), None);
+    }
+}
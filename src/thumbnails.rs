@@ -0,0 +1,291 @@
+// thumbnails.rs - The shared thumbnail cache of the Thumbnail Managing Standard
+//
+// Thumbnails live in $XDG_CACHE_HOME/thumbnails/{normal,large}, named after the
+// MD5 of the file's URI, and are shared with file managers and image viewers.
+// A thumbnail records the URI and modification time of its file in PNG text
+// chunks; it is stale once the file changes. Files that could not be
+// thumbnailed get an entry under fail/ so nobody tries again until they change.
+// See https://specifications.freedesktop.org/thumbnail-spec/latest/
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::uri::file_uri;
+use crate::xdg;
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThumbnailSize {
+    Normal,
+    Large,
+}
+
+impl ThumbnailSize {
+    /// The largest width or height of a thumbnail of this size
+    pub fn pixels(self) -> i32 {
+        match self {
+            ThumbnailSize::Normal => 128,
+            ThumbnailSize::Large => 256,
+        }
+    }
+
+    fn dir_name(self) -> &'static str {
+        match self {
+            ThumbnailSize::Normal => "normal",
+            ThumbnailSize::Large => "large",
+        }
+    }
+}
+
+/// What the cache knows about a file
+#[derive(Debug, PartialEq)]
+pub enum Lookup {
+    Found(PathBuf),
+    /// Thumbnailing failed before and the file has not changed since
+    Failed,
+    Missing,
+}
+
+/// A file to thumbnail, identified as the spec requires
+#[derive(Debug, Clone)]
+pub struct ThumbnailSource {
+    pub uri: String,
+    pub mtime: u64, // Seconds since the epoch
+    pub size: u64,
+}
+
+impl ThumbnailSource {
+    pub fn new(path: &Path) -> Option<Self> {
+        let path = fs::canonicalize(path).ok()?;
+        let metadata = fs::metadata(&path).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            uri: file_uri(&path),
+            mtime: mtime.as_secs(),
+            size: metadata.len(),
+        })
+    }
+
+    /// The text chunks every thumbnail (and fail entry) of this file carries
+    pub fn attributes(&self) -> Vec<(String, String)> {
+        vec![
+            ("tEXt::Thumb::URI".to_string(), self.uri.clone()),
+            ("tEXt::Thumb::MTime".to_string(), self.mtime.to_string()),
+            ("tEXt::Thumb::Size".to_string(), self.size.to_string()),
+            ("tEXt::Software".to_string(), "Synaptrix".to_string()),
+        ]
+    }
+
+    /// Whether the PNG at `thumbnail` was made from this version of the file
+    fn is_current(&self, thumbnail: &Path) -> bool {
+        let Ok(png) = fs::read(thumbnail) else {
+            return false;
+        };
+        let Some(text) = png_text(&png) else {
+            return false;
+        };
+        let mtime_matches = text.get("Thumb::MTime") == Some(&self.mtime.to_string());
+        // The URI is required too, but only checked when present, like GLib does
+        let uri_matches = text.get("Thumb::URI").map_or(true, |uri| *uri == self.uri);
+        mtime_matches && uri_matches
+    }
+}
+
+pub struct ThumbnailCache {
+    root: PathBuf,
+}
+
+impl ThumbnailCache {
+    /// The cache in $XDG_CACHE_HOME/thumbnails
+    pub fn new() -> Self {
+        Self::at(xdg::cache_home().join("thumbnails"))
+    }
+
+    pub fn at(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn path(&self, source: &ThumbnailSource, size: ThumbnailSize) -> PathBuf {
+        self.root.join(size.dir_name()).join(file_name(&source.uri))
+    }
+
+    /// Failures are recorded per application and version, as other thumbnailers may succeed
+    pub fn fail_path(&self, source: &ThumbnailSource) -> PathBuf {
+        self.root
+            .join("fail")
+            .join(concat!("synaptrix-", env!("CARGO_PKG_VERSION")))
+            .join(file_name(&source.uri))
+    }
+
+    /// A current thumbnail of at least `size`, or whether creating one failed before
+    pub fn lookup(&self, source: &ThumbnailSource, size: ThumbnailSize) -> Lookup {
+        let sizes: &[ThumbnailSize] = match size {
+            // A larger thumbnail is just as good when scaled down
+            ThumbnailSize::Normal => &[ThumbnailSize::Normal, ThumbnailSize::Large],
+            ThumbnailSize::Large => &[ThumbnailSize::Large],
+        };
+        for size in sizes {
+            let thumbnail = self.path(source, *size);
+            if source.is_current(&thumbnail) {
+                return Lookup::Found(thumbnail);
+            }
+        }
+        if source.is_current(&self.fail_path(source)) {
+            return Lookup::Failed;
+        }
+        Lookup::Missing
+    }
+}
+
+impl Default for ThumbnailCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn file_name(uri: &str) -> String {
+    format!("{:x}.png", md5::compute(uri.as_bytes()))
+}
+
+/// The tEXt chunks before the image data of a PNG file. CRCs are not checked.
+pub fn png_text(png: &[u8]) -> Option<HashMap<String, String>> {
+    let mut rest = png.strip_prefix(PNG_SIGNATURE)?;
+    let mut text = HashMap::new();
+    loop {
+        let length = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let kind = rest.get(4..8)?;
+        let data = rest.get(8..8 + length)?;
+        match kind {
+            b"tEXt" => {
+                // keyword NUL value, both Latin-1
+                if let Some(nul) = data.iter().position(|b| *b == 0) {
+                    text.insert(latin1(&data[..nul]), latin1(&data[nul + 1..]));
+                }
+            }
+            // The spec puts the attributes before the image data
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+        rest = rest.get(8 + length + 4..)?;
+    }
+    Some(text)
+}
+
+fn latin1(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0; 4]); // CRC, not checked
+        chunk
+    }
+
+    fn png(text: &[(&str, &str)]) -> Vec<u8> {
+        let mut png = PNG_SIGNATURE.to_vec();
+        png.extend(chunk(b"IHDR", &[0; 13]));
+        for (key, value) in text {
+            png.extend(chunk(b"tEXt", format!("{}\0{}", key, value).as_bytes()));
+        }
+        png.extend(chunk(b"IDAT", &[1, 2, 3]));
+        png.extend(chunk(b"tEXt", b"Late\0ignored"));
+        png.extend(chunk(b"IEND", &[]));
+        png
+    }
+
+    #[test]
+    fn test_png_text_chunks() {
+        let text = png_text(&png(&[("Thumb::MTime", "42"), ("Thumb::URI", "file:///a")])).unwrap();
+        assert_eq!(text.get("Thumb::MTime").map(String::as_str), Some("42"));
+        assert_eq!(
+            text.get("Thumb::URI").map(String::as_str),
+            Some("file:///a")
+        );
+        assert!(!text.contains_key("Late"));
+        assert!(png_text(b"GIF89a").is_none());
+        // Truncated files are rejected rather than read past their end
+        let truncated = png(&[("Thumb::MTime", "42")]);
+        assert!(png_text(&truncated[..40]).is_none());
+    }
+
+    #[test]
+    fn test_names_follow_the_spec() {
+        let source = ThumbnailSource {
+            uri: "file:///home/jens/photos/me.png".to_string(),
+            mtime: 0,
+            size: 0,
+        };
+        let cache = ThumbnailCache::at(PathBuf::from("/cache/thumbnails"));
+        // The example from the spec
+        assert_eq!(
+            cache.path(&source, ThumbnailSize::Normal),
+            PathBuf::from("/cache/thumbnails/normal/c6ee772d9e49320e97ec29a7eb5b1697.png")
+        );
+        let fail = cache.fail_path(&source);
+        assert!(fail.starts_with("/cache/thumbnails/fail"));
+        assert!(fail
+            .parent()
+            .and_then(|dir| dir.file_name())
+            .is_some_and(|dir| dir.to_string_lossy().starts_with("synaptrix-")));
+    }
+
+    #[test]
+    fn test_lookup_validates_modification_time() {
+        let dir = tempdir().unwrap();
+        let photo = dir.path().join("photo.png");
+        fs::write(&photo, "not really a png").unwrap();
+        let source = ThumbnailSource::new(&photo).unwrap();
+        let cache = ThumbnailCache::at(dir.path().join("thumbnails"));
+        assert_eq!(
+            cache.lookup(&source, ThumbnailSize::Normal),
+            Lookup::Missing
+        );
+
+        // A large thumbnail serves normal requests, but not the other way round
+        let large = cache.path(&source, ThumbnailSize::Large);
+        fs::create_dir_all(large.parent().unwrap()).unwrap();
+        let mtime = source.mtime.to_string();
+        fs::write(
+            &large,
+            png(&[("Thumb::URI", &source.uri), ("Thumb::MTime", &mtime)]),
+        )
+        .unwrap();
+        assert_eq!(
+            cache.lookup(&source, ThumbnailSize::Normal),
+            Lookup::Found(large.clone())
+        );
+
+        // A thumbnail of an older version of the file is stale
+        let changed = ThumbnailSource {
+            mtime: source.mtime + 1,
+            ..source.clone()
+        };
+        assert_eq!(
+            cache.lookup(&changed, ThumbnailSize::Normal),
+            Lookup::Missing
+        );
+
+        let fail = cache.fail_path(&changed);
+        fs::create_dir_all(fail.parent().unwrap()).unwrap();
+        let mtime = changed.mtime.to_string();
+        fs::write(
+            &fail,
+            png(&[("Thumb::URI", &changed.uri), ("Thumb::MTime", &mtime)]),
+        )
+        .unwrap();
+        assert_eq!(
+            cache.lookup(&changed, ThumbnailSize::Normal),
+            Lookup::Failed
+        );
+        assert_eq!(cache.lookup(&changed, ThumbnailSize::Large), Lookup::Failed);
+    }
+}
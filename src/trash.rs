@@ -0,0 +1,260 @@
+// trash.rs - Move files to the trash as described by the FreeDesktop.org Trash spec
+//
+// Files on the same file system as the home trash ($XDG_DATA_HOME/Trash) go
+// there; others go to the trash at the top of their own mount, so trashing
+// never copies data between file systems. Every trashed file gets a
+// .trashinfo file recording where it came from, which is what file managers
+// use to restore it. See https://specifications.freedesktop.org/trash-spec/latest/
+use std::error::Error;
+use std::fs;
+use std::io::{self, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::path::{Path, PathBuf};
+
+use crate::uri::percent_encode_path;
+use crate::xdg;
+
+// The sticky bit a shared $topdir/.Trash must have to be used
+const STICKY: u32 = 0o1000;
+
+/// Move `path` to the appropriate trash. Returns its new location.
+pub fn move_to_trash(path: &Path) -> Result<PathBuf, Box<dyn Error>> {
+    let home_trash = xdg::data_home().join("Trash");
+    trash_file(path, &home_trash, &local_timestamp(crate::history::now()))
+}
+
+/// Trash `path` into `home_trash` or its mount's trash, with `deletion_date` in the info file
+fn trash_file(
+    path: &Path,
+    home_trash: &Path,
+    deletion_date: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    if !path.is_absolute() {
+        return Err(format!("Cannot trash relative path {}", path.display()).into());
+    }
+    let name = path.file_name().ok_or("Cannot trash a file system root")?;
+    // A symbolic link is trashed itself, not its target
+    let metadata = fs::symlink_metadata(path)?;
+
+    create_private_dir(home_trash)?;
+    let (trash, info_path) = if fs::metadata(home_trash)?.dev() == metadata.dev() {
+        (home_trash.to_path_buf(), path.to_path_buf())
+    } else {
+        // Paths in a mount's trash are relative to the mount, so they survive remounting
+        let topdir = mount_point(path, metadata.dev())?;
+        // SAFETY: getuid takes no arguments, cannot fail and touches no memory of ours
+        let uid = unsafe { libc::getuid() };
+        let trash = topdir_trash(&topdir, uid)?;
+        let relative = path.strip_prefix(&topdir)?.to_path_buf();
+        (trash, relative)
+    };
+
+    let files_dir = trash.join("files");
+    let info_dir = trash.join("info");
+    create_private_dir(&files_dir)?;
+    create_private_dir(&info_dir)?;
+
+    // Claim a unique name by creating its info file first, as the spec requires
+    let name = name.to_string_lossy();
+    let mut attempt = 1;
+    let (trash_name, info_file) = loop {
+        let candidate = match attempt {
+            1 => name.to_string(),
+            n => format!("{}.{}", name, n),
+        };
+        let info_file = info_dir.join(format!("{}.trashinfo", candidate));
+        match fs::File::options()
+            .write(true)
+            .create_new(true)
+            .open(&info_file)
+        {
+            Ok(mut file) => {
+                let written = write!(
+                    file,
+                    "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                    percent_encode_path(&info_path),
+                    deletion_date
+                );
+                if let Err(e) = written {
+                    let _ = fs::remove_file(&info_file);
+                    return Err(e.into());
+                }
+                break (candidate, info_file);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    let destination = files_dir.join(&trash_name);
+    if let Err(e) = fs::rename(path, &destination) {
+        let _ = fs::remove_file(&info_file);
+        return Err(format!("Could not move {} to the trash: {}", path.display(), e).into());
+    }
+    // println!("DEBUG: Trashed {:?} as {:?}", path, destination);
+    Ok(destination)
+}
+
+/// The trash directory of the mount at `topdir`: $topdir/.Trash/$uid if the
+/// administrator provided a shared, sticky .Trash, otherwise $topdir/.Trash-$uid
+fn topdir_trash(topdir: &Path, uid: u32) -> Result<PathBuf, Box<dyn Error>> {
+    let shared = topdir.join(".Trash");
+    let usable = fs::symlink_metadata(&shared)
+        .map(|metadata| metadata.is_dir() && metadata.mode() & STICKY != 0)
+        .unwrap_or(false);
+    if usable {
+        let trash = shared.join(uid.to_string());
+        if create_private_dir(&trash).is_ok() && owned_dir(&trash, uid) {
+            return Ok(trash);
+        }
+    }
+
+    let trash = topdir.join(format!(".Trash-{}", uid));
+    create_private_dir(&trash)?;
+    if !owned_dir(&trash, uid) {
+        return Err(format!("{} is not a usable trash directory", trash.display()).into());
+    }
+    Ok(trash)
+}
+
+/// The top directory of the file system `path` is on
+fn mount_point(path: &Path, dev: u64) -> Result<PathBuf, Box<dyn Error>> {
+    let mut topdir = path.parent().ok_or("No parent directory")?;
+    while let Some(parent) = topdir.parent() {
+        if fs::metadata(parent)?.dev() != dev {
+            break;
+        }
+        topdir = parent;
+    }
+    Ok(topdir.to_path_buf())
+}
+
+/// A real directory (not a symbolic link) belonging to `uid`
+fn owned_dir(path: &Path, uid: u32) -> bool {
+    fs::symlink_metadata(path)
+        .map(|metadata| metadata.is_dir() && metadata.uid() == uid)
+        .unwrap_or(false)
+}
+
+/// Create `path` and its parents readable only by the user, if it does not exist
+fn create_private_dir(path: &Path) -> io::Result<()> {
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(path)
+}
+
+/// Seconds since the epoch as local time in the YYYY-MM-DDThh:mm:ss form the spec uses
+fn local_timestamp(seconds: u64) -> String {
+    let time = seconds as libc::time_t;
+    // SAFETY: localtime_r only writes to the tm we pass it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return crate::xbel::format_timestamp(seconds)
+            .trim_end_matches('Z')
+            .to_string();
+    }
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_trash_into_home_trash() {
+        let dir = tempdir().unwrap();
+        let home_trash = dir.path().join("data/Trash");
+        let docs = dir.path().join("My Docs");
+        fs::create_dir_all(&docs).unwrap();
+        fs::write(docs.join("a b.txt"), "first").unwrap();
+
+        let trashed =
+            trash_file(&docs.join("a b.txt"), &home_trash, "2024-05-01T10:00:00").unwrap();
+        assert_eq!(trashed, home_trash.join("files/a b.txt"));
+        assert_eq!(fs::read_to_string(&trashed).unwrap(), "first");
+        assert!(!docs.join("a b.txt").exists());
+        assert_eq!(
+            fs::read_to_string(home_trash.join("info/a b.txt.trashinfo")).unwrap(),
+            format!(
+                "[Trash Info]\nPath={}/My%20Docs/a%20b.txt\nDeletionDate=2024-05-01T10:00:00\n",
+                percent_encode_path(dir.path())
+            )
+        );
+        assert_eq!(
+            fs::metadata(home_trash.join("files"))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o777,
+            0o700
+        );
+
+        // A second file with the same name does not overwrite the first
+        fs::write(docs.join("a b.txt"), "second").unwrap();
+        let trashed =
+            trash_file(&docs.join("a b.txt"), &home_trash, "2024-05-01T10:00:01").unwrap();
+        assert_eq!(trashed, home_trash.join("files/a b.txt.2"));
+        assert!(home_trash.join("info/a b.txt.2.trashinfo").exists());
+        assert_eq!(
+            fs::read_to_string(home_trash.join("files/a b.txt")).unwrap(),
+            "first"
+        );
+    }
+
+    #[test]
+    fn test_failed_trash_leaves_no_info_file() {
+        let dir = tempdir().unwrap();
+        let home_trash = dir.path().join("Trash");
+        assert!(trash_file(&dir.path().join("missing"), &home_trash, "").is_err());
+        assert!(trash_file(Path::new("relative.txt"), &home_trash, "").is_err());
+        assert!(!home_trash.join("info/missing.trashinfo").exists());
+    }
+
+    #[test]
+    fn test_topdir_trash_selection() {
+        // SAFETY: getuid takes no arguments, cannot fail and touches no memory of ours
+        let uid = unsafe { libc::getuid() };
+
+        // Without an administrator-provided .Trash, the per-user directory is used
+        let topdir = tempdir().unwrap();
+        assert_eq!(
+            topdir_trash(topdir.path(), uid).unwrap(),
+            topdir.path().join(format!(".Trash-{}", uid))
+        );
+
+        // A .Trash without the sticky bit is not safe to share
+        let topdir = tempdir().unwrap();
+        let shared = topdir.path().join(".Trash");
+        fs::create_dir(&shared).unwrap();
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o777)).unwrap();
+        assert_eq!(
+            topdir_trash(topdir.path(), uid).unwrap(),
+            topdir.path().join(format!(".Trash-{}", uid))
+        );
+
+        fs::set_permissions(&shared, fs::Permissions::from_mode(0o1777)).unwrap();
+        assert_eq!(
+            topdir_trash(topdir.path(), uid).unwrap(),
+            shared.join(uid.to_string())
+        );
+    }
+
+    #[test]
+    fn test_local_timestamp_format() {
+        let timestamp = local_timestamp(1_700_000_000);
+        assert_eq!(timestamp.len(), "2023-11-14T22:13:20".len());
+        assert!(timestamp.starts_with("2023-11-1"));
+        assert_eq!(&timestamp[10..11], "T");
+    }
+}
@@ -0,0 +1,283 @@
+// units.rs - Unit conversions such as "5 mi to km" or "1h30m in s"
+//
+// Every unit is a factor (and for temperatures an offset) relative to the base
+// unit of its dimension, so any two units of a dimension convert through it.
+// Queries arrive lowercased, so "mb" is a megabyte and megabits are "mbit".
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dimension {
+    Length,      // Meters
+    Mass,        // Kilograms
+    Temperature, // Kelvin
+    Data,        // Bytes
+    Time,        // Seconds
+    Speed,       // Meters per second
+}
+
+#[derive(Debug)]
+pub struct Unit {
+    pub names: &'static [&'static str], // The first one is shown in results
+    pub dimension: Dimension,
+    factor: f64,
+    offset: f64, // base = value * factor + offset
+}
+
+impl Unit {
+    pub fn symbol(&self) -> &'static str {
+        self.names[0]
+    }
+
+    fn base_value(&self, value: f64) -> f64 {
+        value * self.factor + self.offset
+    }
+
+    fn value_from_base(&self, value: f64) -> f64 {
+        (value - self.offset) / self.factor
+    }
+}
+
+const fn unit(names: &'static [&'static str], dimension: Dimension, factor: f64) -> Unit {
+    Unit {
+        names,
+        dimension,
+        factor,
+        offset: 0.0,
+    }
+}
+
+const KIB: f64 = 1024.0;
+
+#[rustfmt::skip]
+const UNITS: &[Unit] = &[
+    unit(&["nm", "nanometer", "nanometers"], Dimension::Length, 1e-9),
+    unit(&["µm", "um", "micrometer", "micrometers"], Dimension::Length, 1e-6),
+    unit(&["mm", "millimeter", "millimeters"], Dimension::Length, 0.001),
+    unit(&["cm", "centimeter", "centimeters"], Dimension::Length, 0.01),
+    unit(&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    unit(&["km", "kilometer", "kilometers"], Dimension::Length, 1000.0),
+    unit(&["in", "inch", "inches", "\""], Dimension::Length, 0.0254),
+    unit(&["ft", "foot", "feet", "'"], Dimension::Length, 0.3048),
+    unit(&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    unit(&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    unit(&["nmi", "nautical mile", "nautical miles"], Dimension::Length, 1852.0),
+    unit(&["mg", "milligram", "milligrams"], Dimension::Mass, 1e-6),
+    unit(&["g", "gram", "grams"], Dimension::Mass, 0.001),
+    unit(&["kg", "kilogram", "kilograms", "kilo", "kilos"], Dimension::Mass, 1.0),
+    unit(&["t", "tonne", "tonnes"], Dimension::Mass, 1000.0),
+    unit(&["oz", "ounce", "ounces"], Dimension::Mass, 0.028349523125),
+    unit(&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 0.45359237),
+    unit(&["st", "stone", "stones"], Dimension::Mass, 6.35029318),
+    Unit {
+        names: &["°C", "c", "°c", "celsius", "degc"],
+        dimension: Dimension::Temperature,
+        factor: 1.0,
+        offset: 273.15,
+    },
+    Unit {
+        names: &["°F", "f", "°f", "fahrenheit", "degf"],
+        dimension: Dimension::Temperature,
+        factor: 5.0 / 9.0,
+        offset: 459.67 * 5.0 / 9.0,
+    },
+    unit(&["K", "k", "kelvin"], Dimension::Temperature, 1.0),
+    unit(&["bit", "bits"], Dimension::Data, 0.125),
+    unit(&["B", "b", "byte", "bytes"], Dimension::Data, 1.0),
+    unit(&["kbit", "kbits"], Dimension::Data, 125.0),
+    unit(&["Mbit", "mbit", "mbits"], Dimension::Data, 125e3),
+    unit(&["Gbit", "gbit", "gbits"], Dimension::Data, 125e6),
+    unit(&["kB", "kb", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    unit(&["MB", "mb", "megabyte", "megabytes"], Dimension::Data, 1e6),
+    unit(&["GB", "gb", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    unit(&["TB", "tb", "terabyte", "terabytes"], Dimension::Data, 1e12),
+    unit(&["PB", "pb", "petabyte", "petabytes"], Dimension::Data, 1e15),
+    unit(&["KiB", "kib", "kibibyte", "kibibytes"], Dimension::Data, KIB),
+    unit(&["MiB", "mib", "mebibyte", "mebibytes"], Dimension::Data, KIB * KIB),
+    unit(&["GiB", "gib", "gibibyte", "gibibytes"], Dimension::Data, KIB * KIB * KIB),
+    unit(&["TiB", "tib", "tebibyte", "tebibytes"], Dimension::Data, KIB * KIB * KIB * KIB),
+    unit(&["ns", "nanosecond", "nanoseconds"], Dimension::Time, 1e-9),
+    unit(&["µs", "us", "microsecond", "microseconds"], Dimension::Time, 1e-6),
+    unit(&["ms", "millisecond", "milliseconds"], Dimension::Time, 0.001),
+    unit(&["s", "sec", "secs", "second", "seconds"], Dimension::Time, 1.0),
+    unit(&["min", "mins", "minute", "minutes"], Dimension::Time, 60.0),
+    unit(&["h", "hr", "hrs", "hour", "hours"], Dimension::Time, 3600.0),
+    unit(&["d", "day", "days"], Dimension::Time, 86400.0),
+    unit(&["wk", "week", "weeks"], Dimension::Time, 604800.0),
+    unit(&["yr", "year", "years"], Dimension::Time, 31557600.0), // Julian year of 365.25 days
+    unit(&["m/s", "mps"], Dimension::Speed, 1.0),
+    unit(&["km/h", "kph", "kmh"], Dimension::Speed, 1.0 / 3.6),
+    unit(&["mph", "mi/h"], Dimension::Speed, 0.44704),
+    unit(&["ft/s", "fps"], Dimension::Speed, 0.3048),
+    unit(&["kn", "kt", "knot", "knots"], Dimension::Speed, 1852.0 / 3600.0),
+];
+
+// Words between the quantity and the unit to convert to
+const SEPARATORS: [&str; 3] = [" to ", " in ", " as "];
+
+/// The unit called `name`. In a duration like "1h30m", "m" means minutes.
+pub fn find_unit(name: &str, dimension: Option<Dimension>) -> Option<&'static Unit> {
+    let name = name.trim();
+    if name == "m" && dimension == Some(Dimension::Time) {
+        return find_unit("min", None);
+    }
+    UNITS
+        .iter()
+        .find(|unit| unit.names.contains(&name))
+        .or_else(|| {
+            let lowercase = name.to_lowercase();
+            UNITS
+                .iter()
+                .find(|unit| unit.names.contains(&lowercase.as_str()))
+        })
+}
+
+/// A conversion parsed from a query such as "5 mi to km"
+#[derive(Debug)]
+pub struct Conversion {
+    pub quantity: String, // As typed, e.g. "1h30m"
+    pub value: f64,
+    pub unit: &'static Unit,
+}
+
+/// Convert the quantity in `query` to the unit after its last "to", "in" or "as".
+/// Returns None for queries that are not conversions at all.
+pub fn convert(query: &str) -> Option<Result<Conversion, Box<dyn Error>>> {
+    let (quantity, target) = SEPARATORS
+        .iter()
+        .filter_map(|separator| query.rfind(separator).map(|i| (i, separator.len())))
+        .max()
+        .map(|(i, len)| (query[..i].trim(), query[i + len..].trim()))?;
+    let segments = parse_quantity(quantity)?;
+
+    // A duration written as "1h30m" decides what its "m" means
+    let is_time = |segments: &[(f64, &str)]| {
+        segments.len() > 1
+            && segments.iter().any(|(_, name)| {
+                find_unit(name, None).is_some_and(|u| u.dimension == Dimension::Time)
+            })
+    };
+    let context = is_time(&segments).then_some(Dimension::Time);
+    let units: Vec<&Unit> = segments
+        .iter()
+        .map(|(_, name)| find_unit(name, context))
+        .collect::<Option<_>>()?;
+    let dimension = units[0].dimension;
+    let target = find_unit(target, Some(dimension))?;
+
+    Some(combine(&segments, &units, target).map(|value| Conversion {
+        quantity: quantity.to_string(),
+        value,
+        unit: target,
+    }))
+}
+
+fn combine(
+    segments: &[(f64, &str)],
+    units: &[&Unit],
+    target: &'static Unit,
+) -> Result<f64, Box<dyn Error>> {
+    let dimension = units[0].dimension;
+    if units.iter().any(|unit| unit.dimension != dimension) || target.dimension != dimension {
+        return Err(format!(
+            "Cannot convert {:?} to {:?}",
+            units[0].symbol(),
+            target.symbol()
+        )
+        .into());
+    }
+    if dimension == Dimension::Temperature && units.len() > 1 {
+        return Err("Temperatures cannot be added up".into());
+    }
+    let base: f64 = segments
+        .iter()
+        .zip(units)
+        .map(|((value, _), unit)| unit.base_value(*value))
+        .sum();
+    Ok(target.value_from_base(base))
+}
+
+/// "5 mi", "72f", "-3.5 °C" or "1h 30m" as (number, unit name) pairs
+fn parse_quantity(quantity: &str) -> Option<Vec<(f64, &str)>> {
+    let mut segments = Vec::new();
+    let mut rest = quantity.trim();
+    while !rest.is_empty() {
+        let sign = usize::from(segments.is_empty() && rest.starts_with('-'));
+        let number_end = sign
+            + rest[sign..]
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len() - sign);
+        let value = rest[..number_end].parse().ok()?;
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let name = rest[..unit_end].trim();
+        if name.is_empty() {
+            return None;
+        }
+        segments.push((value, name));
+        rest = &rest[unit_end..];
+    }
+    (!segments.is_empty()).then_some(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn converted(query: &str) -> (f64, &'static str) {
+        let conversion = convert(query).unwrap().unwrap();
+        (conversion.value, conversion.unit.symbol())
+    }
+
+    fn assert_close(query: &str, expected: f64, symbol: &str) {
+        let (value, unit) = converted(query);
+        assert!(
+            (value - expected).abs() < 1e-9 * expected.abs().max(1.0),
+            "{} gave {}",
+            query,
+            value
+        );
+        assert_eq!(unit, symbol);
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_close("5 mi to km", 8.04672, "km");
+        assert_close("72f in c", 22.222222222222, "°C");
+        assert_close("-40 °c to fahrenheit", -40.0, "°F");
+        assert_close("0 k in c", -273.15, "°C");
+        assert_close("2gib in mb", 2147.483648, "MB");
+        assert_close("100 mbit in mb", 12.5, "MB");
+        assert_close("1h30m in s", 5400.0, "s");
+        assert_close("1 h 30 min in h", 1.5, "h");
+        assert_close("90 s in m", 1.5, "min");
+        assert_close("100 km/h to mph", 62.137119223733, "mph");
+        assert_close("5 ft 11 in in cm", 180.34, "cm");
+        assert_close("1 lb as g", 453.59237, "g");
+    }
+
+    #[test]
+    fn test_not_conversions() {
+        for query in [
+            "5 mi",
+            "firefox",
+            "ls to dir",
+            "install in home",
+            "2 + 3 in 4",
+        ] {
+            assert!(convert(query).is_none(), "{} is not a conversion", query);
+        }
+    }
+
+    #[test]
+    fn test_invalid_conversions() {
+        for query in ["5 kg to km", "10 c 5 f in k", "3 s in kb"] {
+            assert!(
+                matches!(convert(query), Some(Err(_))),
+                "{} should fail",
+                query
+            );
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// uri.rs - file:// URI conversion with percent-encoding
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+
+/// Characters left as-is in the path component of a file:// URI (RFC 3986 unreserved plus "/")
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/')
+}
+
+/// Convert an absolute path to a percent-encoded file:// URI
+pub fn file_uri(path: &Path) -> String {
+    format!("file://{}", percent_encode_path(path))
+}
+
+/// Percent-encode a path as in the path component of a URI, keeping the slashes
+pub fn percent_encode_path(path: &Path) -> String {
+    let mut encoded = String::new();
+    for &byte in path.as_os_str().as_bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// Convert a file:// URI (optionally with an empty or "localhost" host) back to a path
+pub fn file_path_from_uri(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let path = if rest.starts_with('/') {
+        rest
+    } else {
+        rest.strip_prefix("localhost")?
+    };
+    let bytes = percent_decode(path)?;
+    Some(PathBuf::from(OsStr::from_bytes(&bytes)))
+}
+
+/// Decode %XX sequences; returns None for malformed escapes
+pub fn percent_decode(value: &str) -> Option<Vec<u8>> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = value.get(i + 1..i + 3)?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path = Path::new("/home/user/My Documents/naïve #1 (draft)%.txt");
+        let uri = file_uri(path);
+        assert_eq!(
+            uri,
+            "file:///home/user/My%20Documents/na%C3%AFve%20%231%20%28draft%29%25.txt"
+        );
+        assert_eq!(file_path_from_uri(&uri).as_deref(), Some(path));
+    }
+
+    #[test]
+    fn test_hosts_and_invalid_uris() {
+        assert_eq!(
+            file_path_from_uri("file://localhost/tmp/a%20b").as_deref(),
+            Some(Path::new("/tmp/a b"))
+        );
+        assert!(file_path_from_uri("file://otherhost/tmp/a").is_none());
+        assert!(file_path_from_uri("https://example.com/").is_none());
+        assert!(file_path_from_uri("file:///tmp/bad%2").is_none());
+        assert!(file_path_from_uri("file:///tmp/bad%zz").is_none());
+    }
+}
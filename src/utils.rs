@@ -1,96 +1,249 @@
 use gtk::Image;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::fs;
-
-pub fn launch_application(exec: &str) {
-    let mut cmd = if exec.contains("pkexec") {
-        let parts: Vec<&str> = exec.split_whitespace().collect();
-        let mut command = Command::new("pkexec");
-        for part in parts.iter().skip(1) {
-            command.arg(part);
-        }
-        command
+use std::os::unix::fs::DirBuilderExt;
+
+use crate::app_info::AppInfo;
+use crate::exec::{self, LaunchContext};
+use crate::history;
+use crate::icon_theme::IconResolver;
+use crate::settings::LauncherSettings;
+use crate::spawner::spawn_detached;
+use crate::terminal::terminal_command;
+use crate::thumbnails::{Lookup, ThumbnailCache, ThumbnailSize, ThumbnailSource};
+use crate::xbel;
+
+pub fn launch_application(
+    app: &AppInfo,
+    settings: &LauncherSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    launch_application_with_files(app, &[], settings)
+}
+
+/// Launch an entry, substituting `files` for the %f/%F/%u/%U field codes of its Exec line
+pub fn launch_application_with_files(
+    app: &AppInfo,
+    files: &[PathBuf],
+    settings: &LauncherSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let context = LaunchContext {
+        files,
+        icon: app.icon.as_deref(),
+        name: &app.name,
+        desktop_file: app.source_path.as_deref(),
+    };
+
+    // Terminal=true entries (htop, vim, ...) need an emulator to be visible at all
+    let terminal = if app.terminal {
+        Some(
+            terminal_command(&settings.behavior.terminal)
+                .ok_or("No terminal emulator found; set behavior.terminal in settings.yaml")?,
+        )
     } else {
-        let parts: Vec<&str> = exec.split_whitespace().collect();
-        if let Some(program) = parts.first() {
-            let mut command = Command::new(program);
-            for arg in parts.iter().skip(1) {
-                command.arg(arg);
-            }
-            command
-        } else {
-            return;
-        }
+        None
     };
 
-    // Detach from parent process
-    cmd.spawn().unwrap_or_else(|e| {
-        eprintln!("Failed to launch application: {}", e);
-        std::process::exit(1);
-    });
+    for argv in exec::command_lines(&app.exec, &context)? {
+        let argv = match terminal {
+            Some(ref prefix) => prefix.iter().cloned().chain(argv).collect(),
+            None => argv,
+        };
+        spawn_detached(&argv, app.working_dir.as_deref())?;
+    }
+    Ok(())
 }
 
-pub fn open_file(path: &Path) {
+pub fn open_file(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
     // Use xdg-open to open files with default applications
-    Command::new("xdg-open")
-        .arg(path)
-        .spawn()
-        .unwrap_or_else(|e| {
-            eprintln!("Failed to open file: {}", e);
-            std::process::exit(1);
-        });
+    spawn_detached(&["xdg-open".to_string(), path.to_string_lossy().into_owned()], None)?;
+    Ok(())
 }
 
-pub fn is_image_file(path: &Path) -> bool {
-    if let Some(ext) = path.extension() {
-        matches!(
-            ext.to_str().unwrap_or("").to_lowercase().as_str(),
-            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "webp" | "svg"
-        )
-    } else {
-        false
+const FILE_MANAGER_BUS_NAME: &str = "org.freedesktop.FileManager1";
+
+/// Show `path` selected in the file manager through the org.freedesktop.FileManager1
+/// D-Bus interface, or open the folder containing it if no file manager provides that.
+/// Every D-Bus step is asynchronous, so a missing or slow service never blocks the window.
+pub fn show_in_folder(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    use gtk::gio;
+    use gtk::prelude::*;
+
+    let folder = path.parent().ok_or("The file is not in a folder")?.to_path_buf();
+    let uri = crate::uri::file_uri(path);
+    let show_items = move |connection: Option<gio::DBusConnection>| {
+        let Some(connection) = connection else {
+            // println!("DEBUG: No session bus, opening the folder");
+            open_folder(&folder);
+            return;
+        };
+        let parameters = (vec![uri], String::new()).to_variant();
+        connection.call(
+            Some(FILE_MANAGER_BUS_NAME),
+            "/org/freedesktop/FileManager1",
+            FILE_MANAGER_BUS_NAME,
+            "ShowItems",
+            Some(&parameters),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+            gio::Cancellable::NONE,
+            move |result| {
+                // No file manager provides the interface, or it could not show the file
+                if result.is_err() {
+                    open_folder(&folder);
+                }
+            },
+        );
+    };
+
+    // Reuse the application's connection; only connect if it has none
+    match gio::Application::default().and_then(|app| app.dbus_connection()) {
+        Some(connection) => show_items(Some(connection)),
+        None => gio::bus_get(
+            gio::BusType::Session,
+            gio::Cancellable::NONE,
+            move |result| show_items(result.ok()),
+        ),
     }
+    Ok(())
 }
 
-pub fn create_thumbnail(path: &Path, size: i32) -> Result<PathBuf, Box<dyn std::error::Error>> {
-    let cache_dir = format!(
-        "{}/.cache/launcher-thumbnails",
-        std::env::var("HOME").unwrap_or_default()
-    );
-    fs::create_dir_all(&cache_dir)?;
-
-    let filename = format!("{:x}", md5::compute(path.to_string_lossy().as_bytes()));
-    let thumbnail_path = PathBuf::from(&cache_dir).join(format!("{}.png", filename));
-
-    if !thumbnail_path.exists() {
-        // Use ImageMagick to create thumbnail
-        Command::new("convert")
-            .arg(path)
-            .arg("-thumbnail")
-            .arg(format!("{}x{}", size, size))
-            .arg(&thumbnail_path)
-            .output()?;
+fn open_folder(folder: &Path) {
+    if let Err(e) = open_file(folder) {
+        eprintln!("Warning: Could not open {}: {}", folder.display(), e);
     }
+}
 
-    Ok(thumbnail_path)
-}
-
-pub fn get_file_icon(path: &Path) -> Option<String> {
-    match path.extension()?.to_str()? {
-        "pdf" => Some("application-pdf".to_string()),
-        "doc" | "docx" => Some("application-msword".to_string()),
-        "xls" | "xlsx" => Some("application-vnd.ms-excel".to_string()),
-        "ppt" | "pptx" => Some("application-vnd.ms-powerpoint".to_string()),
-        "txt" => Some("text-x-generic".to_string()),
-        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" => Some("image-x-generic".to_string()),
-        "mp3" | "wav" | "ogg" | "flac" => Some("audio-x-generic".to_string()),
-        "mp4" | "avi" | "mkv" | "mov" | "webm" => Some("video-x-generic".to_string()),
-        "zip" | "tar" | "gz" | "rar" | "7z" => Some("package-x-generic".to_string()),
-        _ => Some("text-x-generic".to_string()),
+/// Put `text` on the clipboard. The launcher provides the contents while it runs,
+/// or a clipboard manager keeps them afterwards.
+pub fn copy_text(text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use gtk::prelude::*;
+
+    let display = gtk::gdk::Display::default().ok_or("No display to copy to")?;
+    display.clipboard().set_text(text);
+    Ok(())
+}
+
+/// Put files on the clipboard, so file managers paste copies of them
+pub fn copy_files(paths: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    use gtk::gdk;
+    use gtk::glib;
+    use gtk::prelude::*;
+
+    let uris: Vec<String> = paths.iter().map(|path| crate::uri::file_uri(path)).collect();
+    // RFC 2483 lines end in CRLF
+    let uri_list: String = uris.iter().map(|uri| format!("{}\r\n", uri)).collect();
+    // Nautilus, Nemo and Caja only paste files offered in their own format
+    let copied_files = format!("copy\n{}", uris.join("\n"));
+    let text = paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let content = gdk::ContentProvider::new_union(&[
+        gdk::ContentProvider::for_bytes(
+            "text/uri-list",
+            &glib::Bytes::from_owned(uri_list.into_bytes()),
+        ),
+        gdk::ContentProvider::for_bytes(
+            "x-special/gnome-copied-files",
+            &glib::Bytes::from_owned(copied_files.into_bytes()),
+        ),
+        gdk::ContentProvider::for_value(&text.to_value()),
+    ]);
+    let display = gdk::Display::default().ok_or("No display to copy to")?;
+    display.clipboard().set_content(Some(&content))?;
+    Ok(())
+}
+
+/// Add `path` to recently-used.xbel under the application that opened it, or the one
+/// that opens it by default, so file managers and the launcher's own recent files learn from it
+pub fn add_to_recent_files(path: &Path, app: Option<&AppInfo>, settings: &LauncherSettings) {
+    use gtk::gio;
+    use gtk::prelude::*;
+
+    let mime_type = crate::mime::database().detect(path);
+    let (app_name, app_exec) = match app {
+        Some(app) => (app.name.clone(), app.exec.clone()),
+        None => match gio::AppInfo::default_for_type(&mime_type, false) {
+            Some(app) => (app.name().to_string(), format!("{} %u", app.executable().display())),
+            None => ("xdg-open".to_string(), "xdg-open %u".to_string()),
+        },
+    };
+
+    if let Err(e) = xbel::record_opened(
+        Path::new(&settings.recent_files.xbel_path),
+        path,
+        &mime_type,
+        &app_name,
+        &app_exec,
+        history::now(),
+    ) {
+        eprintln!("Warning: Could not add {} to recent files: {}", path.display(), e);
     }
 }
 
+/// Create the normal-size thumbnail of an image in the shared thumbnail cache with
+/// gdk-pixbuf. A failure is recorded in the cache as well, so it is not retried
+/// until the file changes.
+pub fn create_thumbnail(source: &ThumbnailSource) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let cache = ThumbnailCache::new();
+    let path = crate::uri::file_path_from_uri(&source.uri).ok_or("Not a local file")?;
+
+    match scaled_image(&path, ThumbnailSize::Normal.pixels()) {
+        Ok(pixbuf) => {
+            let thumbnail = cache.path(source, ThumbnailSize::Normal);
+            save_png(&pixbuf, &thumbnail, source)?;
+            Ok(thumbnail)
+        }
+        Err(e) => {
+            let failed = gtk::gdk_pixbuf::Pixbuf::new(gtk::gdk_pixbuf::Colorspace::Rgb, true, 8, 1, 1)
+                .ok_or("Could not create an image")?;
+            save_png(&failed, &cache.fail_path(source), source)?;
+            Err(e)
+        }
+    }
+}
+
+/// The image at `path`, upright and scaled down to fit `max_size`
+fn scaled_image(
+    path: &Path,
+    max_size: i32,
+) -> Result<gtk::gdk_pixbuf::Pixbuf, Box<dyn std::error::Error>> {
+    use gtk::gdk_pixbuf::Pixbuf;
+
+    let (_, width, height) = Pixbuf::file_info(path).ok_or("Unsupported image format")?;
+    // Small images are kept at their own size
+    let size = max_size.min(width.max(height)).max(1);
+    let pixbuf = Pixbuf::from_file_at_scale(path, size, size, true)?;
+    // Photos are often stored sideways with an EXIF orientation
+    Ok(pixbuf.apply_embedded_orientation().unwrap_or(pixbuf))
+}
+
+/// Write a thumbnail atomically, readable only by the user, with the spec's attributes
+fn save_png(
+    pixbuf: &gtk::gdk_pixbuf::Pixbuf,
+    destination: &Path,
+    source: &ThumbnailSource,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = destination.parent().ok_or("Invalid thumbnail path")?;
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)?;
+
+    let attributes = source.attributes();
+    let options: Vec<(&str, &str)> = attributes
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+    // Other programs may read the cache at any time; never show them a partial file
+    let temp = tempfile::Builder::new().suffix(".png").tempfile_in(dir)?;
+    pixbuf.savev(temp.path(), "png", &options)?;
+    temp.persist(destination)?;
+    Ok(())
+}
+
 // pub fn find_and_click_button(widget: &gtk::Widget) {
 //     if let Some(button) = widget.downcast_ref::<Button>() {
 //         button.emit_clicked();
@@ -105,11 +258,46 @@ pub fn get_file_icon(path: &Path) -> Option<String> {
 //     }
 // }
 
-pub fn create_icon_widget(
-    icon_name: &Option<String>,
-    item_type: &crate::app_info::ItemType,
-    file_path: &Option<PathBuf>,
-) -> Image {
+thread_local! {
+    /// The icon resolver in use; dropped when the icon theme or its directories change
+    static ICON_RESOLVER: std::cell::RefCell<Option<std::rc::Rc<IconResolver>>> = Default::default();
+}
+
+/// The icon resolver for the icon theme selected in the GTK settings
+fn icon_resolver() -> std::rc::Rc<IconResolver> {
+    use gtk::prelude::*;
+
+    if let Some(resolver) = ICON_RESOLVER.with(|resolver| resolver.borrow().clone()) {
+        return resolver;
+    }
+
+    // Its caches and theme chain go stale when the theme is switched, or themes
+    // are installed or removed; GTK notices both
+    static WATCH_THEME: std::sync::Once = std::sync::Once::new();
+    WATCH_THEME.call_once(|| {
+        let forget = || ICON_RESOLVER.with(|resolver| resolver.borrow_mut().take());
+        if let Some(settings) = gtk::Settings::default() {
+            settings.connect_gtk_icon_theme_name_notify(move |_| {
+                forget();
+            });
+        }
+        if let Some(display) = gtk::gdk::Display::default() {
+            gtk::IconTheme::for_display(&display).connect_changed(move |_| {
+                forget();
+            });
+        }
+    });
+
+    let theme = gtk::Settings::default()
+        .and_then(|settings| settings.gtk_icon_theme_name())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| "hicolor".to_string());
+    let resolver = std::rc::Rc::new(IconResolver::new(&theme));
+    ICON_RESOLVER.with(|current| *current.borrow_mut() = Some(resolver.clone()));
+    resolver
+}
+
+pub fn create_icon_widget(item: &AppInfo) -> Image {
     use crate::app_info::ItemType;
     use gtk::gdk;
     use gtk::prelude::*;
@@ -118,63 +306,131 @@ pub fn create_icon_widget(
     icon.set_pixel_size(48);
     icon.set_valign(gtk::Align::Center);
 
-    // For image files, try to create thumbnails
-    if let Some(path) = file_path {
-        if is_image_file(path) {
-            if let Ok(thumbnail) = create_thumbnail(path, 48) {
-                icon.set_from_file(Some(&thumbnail));
-                return icon;
-            }
+    // Image files show their thumbnail once it has been loaded (or created) in the
+    // background; the regular icon is shown until then
+    let mime_type = item.mime_type.as_deref();
+    if let Some(path) = item.file_path.as_ref().filter(|_| item.item_type == ItemType::RecentFile) {
+        if mime_type.is_some_and(|mime_type| mime_type.starts_with("image/")) {
+            load_thumbnail_in_background(path, &icon);
         }
     }
 
-    if let Some(icon_name) = icon_name {
-        // Try icon theme first (for system menu icons)
-        let theme = gtk::IconTheme::for_display(&gdk::Display::default().unwrap());
-        if theme.has_icon(icon_name) {
-            icon.set_icon_name(Some(icon_name));
-            return icon;
-        }
+    // Files get the icon of their type, or else the generic one of its kind
+    let mut names: Vec<String> = Vec::new();
+    if let Some(mime_type) = mime_type {
+        names.extend(crate::mime::database().icon_names(mime_type));
+    }
+    names.extend(item.icon.clone());
 
-        let icon_paths = vec![
-            // Try exact name first
-            icon_name.clone(),
-            // Try with various sizes in hicolor theme
-            format!("/usr/share/icons/hicolor/48x48/apps/{}.png", icon_name),
-            format!("/usr/share/icons/hicolor/32x32/apps/{}.png", icon_name),
-            format!("/usr/share/icons/hicolor/24x24/apps/{}.png", icon_name),
-            format!("/usr/share/icons/hicolor/scalable/apps/{}.svg", icon_name),
-            // Try in different icon themes
-            format!("/usr/share/icons/Mint-X/apps/48/{}.png", icon_name),
-            format!("/usr/share/icons/Mint-Y/apps/48/{}.png", icon_name),
-            format!("/usr/share/icons/breeze/apps/48/{}.svg", icon_name),
-            format!("/usr/share/icons/oxygen/48x48/apps/{}.png", icon_name),
-            // Try pixmaps
-            format!("/usr/share/pixmaps/{}.png", icon_name),
-            format!("/usr/share/pixmaps/{}.svg", icon_name),
-            format!("/usr/share/pixmaps/{}.xpm", icon_name),
-            // Try flatpak icons
-            format!("/var/lib/flatpak/app/{}/current/active/export/share/icons/hicolor/48x48/apps/{}.png", icon_name, icon_name),
-            // Try application-specific directories
-            format!("/usr/share/{}/icons/{}.png", icon_name, icon_name),
-            format!("/opt/{}/share/icons/{}.png", icon_name, icon_name),
-        ];
-
-        for path in icon_paths {
-            if Path::new(&path).exists() {
-                icon.set_from_file(Some(&path));
-                return icon;
-            }
-        }
+    // Try icon theme first (for system menu icons)
+    let theme = gtk::IconTheme::for_display(&gdk::Display::default().unwrap());
+    if let Some(name) = names.iter().find(|name| theme.has_icon(name)) {
+        icon.set_icon_name(Some(name));
+        return icon;
+    }
+
+    // Then the files GTK does not know: absolute paths, pixmaps, and themes in
+    // directories missing from the environment of the session
+    let resolver = icon_resolver();
+    if let Some(path) = names
+        .iter()
+        .find_map(|name| resolver.lookup(name, 48, icon.scale_factor().max(1) as u32))
+    {
+        icon.set_from_file(Some(&path));
+        return icon;
     }
 
     // Fallback icons based on item type
-    let fallback_icon = match item_type {
+    let fallback_icon = match item.item_type {
         ItemType::Application => "application-x-executable",
         ItemType::Command => "utilities-terminal",
         ItemType::RecentFile => "text-x-generic",
+        ItemType::Plugin | ItemType::FileAction => "system-run",
+        ItemType::Calculator => "accessories-calculator",
     };
 
     icon.set_icon_name(Some(fallback_icon));
     icon
-}
\ No newline at end of file
+}
+
+/// An image to find or create the thumbnail of, and where to send it
+type ThumbnailJob = (PathBuf, tokio::sync::oneshot::Sender<Option<gtk::gdk::Texture>>);
+
+thread_local! {
+    /// The icons waiting for each queued image, so an image is queued only once
+    /// and every row showing it gets the thumbnail
+    static THUMBNAIL_WAITERS: std::cell::RefCell<
+        std::collections::HashMap<PathBuf, Vec<gtk::glib::WeakRef<Image>>>,
+    > = Default::default();
+
+    /// The queue of the thread that looks up and creates thumbnails one at a time,
+    /// started on first use
+    static THUMBNAIL_QUEUE: std::sync::mpsc::Sender<ThumbnailJob> = start_thumbnail_thread();
+}
+
+fn start_thumbnail_thread() -> std::sync::mpsc::Sender<ThumbnailJob> {
+    let (sender, jobs) = std::sync::mpsc::channel::<ThumbnailJob>();
+    let worker = std::thread::Builder::new()
+        .name("synaptrix-thumbnails".to_string())
+        .spawn(move || {
+            for (path, reply) in jobs {
+                let _ = reply.send(thumbnail_texture(&path));
+            }
+        });
+    if let Err(e) = worker {
+        eprintln!("Warning: Could not start the thumbnail thread: {}", e);
+    }
+    sender
+}
+
+/// Look up or create the thumbnail of `path` on the thumbnail thread and show it in `icon`
+fn load_thumbnail_in_background(path: &Path, icon: &Image) {
+    use gtk::glib;
+    use gtk::prelude::*;
+
+    let queued = THUMBNAIL_WAITERS.with(|waiters| {
+        let mut waiters = waiters.borrow_mut();
+        let queued = waiters.contains_key(path);
+        waiters
+            .entry(path.to_path_buf())
+            .or_default()
+            .push(icon.downgrade());
+        queued
+    });
+    if queued {
+        return;
+    }
+
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    let path = path.to_path_buf();
+    // If the thread is gone the reply channel closes, which is handled below
+    let _ = THUMBNAIL_QUEUE.with(|queue| queue.send((path.clone(), sender)));
+
+    glib::MainContext::default().spawn_local(async move {
+        let texture = receiver.await.ok().flatten();
+        let icons = THUMBNAIL_WAITERS
+            .with(|waiters| waiters.borrow_mut().remove(&path))
+            .unwrap_or_default();
+        let Some(texture) = texture else {
+            return;
+        };
+        for icon in icons.iter().filter_map(|icon| icon.upgrade()) {
+            icon.set_from_paintable(Some(&texture));
+        }
+    });
+}
+
+/// The current thumbnail of `path`, created if needed and possible
+fn thumbnail_texture(path: &Path) -> Option<gtk::gdk::Texture> {
+    let source = ThumbnailSource::new(path)?;
+    let thumbnail = match ThumbnailCache::new().lookup(&source, ThumbnailSize::Normal) {
+        Lookup::Found(thumbnail) => thumbnail,
+        Lookup::Failed => return None,
+        Lookup::Missing => {
+            let created = create_thumbnail(&source);
+            // println!("DEBUG: Thumbnail for {:?}: {:?}", path, created);
+            created.ok()?
+        }
+    };
+    gtk::gdk::Texture::from_filename(&thumbnail).ok()
+}
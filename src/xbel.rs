@@ -0,0 +1,649 @@
+// xbel.rs - The freedesktop recently-used.xbel store
+//
+// Format: https://www.freedesktop.org/wiki/Specifications/desktop-bookmark-spec/
+// as written by GLib's GBookmarkFile. Elements are matched by local name, so
+// the namespace prefixes an application chose do not matter.
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::app_info::{XbelApplication, XbelBookmark};
+use crate::uri::file_uri;
+use crate::xml::{self, escape, local_name, XmlEvent};
+
+const DAY: i64 = 24 * 60 * 60;
+
+const FREEDESKTOP_OWNER: &str = "http://freedesktop.org";
+
+/// A whole XBEL document: its bookmarks and what a rewrite has to carry over
+#[derive(Debug, Default, PartialEq)]
+pub struct XbelDocument {
+    pub bookmarks: Vec<XbelBookmark>,
+    pub attributes: Vec<(String, String)>, // Of <xbel>, e.g. namespaces used by other metadata
+    pub other_elements: Vec<String>,       // Children of <xbel> other than bookmarks, as XML
+}
+
+/// Where an element the launcher does not use belongs
+enum Unknown {
+    Document,
+    Element,
+    Metadata,
+    Field,
+}
+
+/// Parse the bookmarks of an XBEL document, in document order
+pub fn parse_xbel(content: &str) -> Result<Vec<XbelBookmark>, Box<dyn Error>> {
+    Ok(parse_xbel_document(content)?.bookmarks)
+}
+
+/// Parse an XBEL document, keeping the elements it does not understand as XML
+pub fn parse_xbel_document(content: &str) -> Result<XbelDocument, Box<dyn Error>> {
+    let mut document = XbelDocument::default();
+    let mut current: Option<XbelBookmark> = None;
+    let mut open: Vec<String> = Vec::new(); // Local names of the enclosing elements
+    let mut text = String::new();
+    // An unknown element being read: where it belongs, its events and open depth
+    let mut unknown: Option<(Unknown, Vec<XmlEvent>, usize)> = None;
+
+    for event in xml::parse(content)? {
+        if let Some((place, mut events, depth)) = unknown.take() {
+            let depth = match &event {
+                XmlEvent::Start { .. } => depth + 1,
+                XmlEvent::End { .. } => depth - 1,
+                XmlEvent::Text(_) => depth,
+            };
+            events.push(event);
+            if depth > 0 {
+                unknown = Some((place, events, depth));
+                continue;
+            }
+            let element = xml::write(&events);
+            match (place, current.as_mut()) {
+                (Unknown::Document, _) => document.other_elements.push(element),
+                (Unknown::Element, Some(bookmark)) => bookmark.other_elements.push(element),
+                (Unknown::Metadata, Some(bookmark)) => bookmark.other_metadata.push(element),
+                (Unknown::Field, Some(bookmark)) => bookmark.other_fields.push(element),
+                _ => {}
+            }
+            continue;
+        }
+
+        if let XmlEvent::Start { name, attributes } = &event {
+            if open.is_empty() {
+                document.attributes = attributes.clone();
+            }
+            let name = local_name(name);
+            let place = match (open.last().map(String::as_str), current.is_some()) {
+                (Some("xbel"), false) if open.len() == 1 && name != "bookmark" => {
+                    Some(Unknown::Document)
+                }
+                (Some("bookmark"), true) if !["title", "desc", "info"].contains(&name) => {
+                    Some(Unknown::Element)
+                }
+                (Some("info"), true)
+                    if name != "metadata"
+                        || event.attribute("owner") != Some(FREEDESKTOP_OWNER) =>
+                {
+                    Some(Unknown::Metadata)
+                }
+                (Some("metadata"), true)
+                    if !["mime-type", "groups", "applications", "private"].contains(&name) =>
+                {
+                    Some(Unknown::Field)
+                }
+                _ => None,
+            };
+            if let Some(place) = place {
+                unknown = Some((place, vec![event], 1));
+                continue;
+            }
+        }
+
+        match &event {
+            XmlEvent::Start { name, .. } => {
+                let name = local_name(name);
+                let timestamp = |attribute| event.attribute(attribute).and_then(parse_timestamp);
+                match (name, current.as_mut()) {
+                    ("bookmark", None) => {
+                        current = Some(XbelBookmark {
+                            href: event.attribute("href").unwrap_or_default().to_string(),
+                            added: timestamp("added"),
+                            modified: timestamp("modified"),
+                            visited: timestamp("visited"),
+                            ..Default::default()
+                        });
+                    }
+                    ("mime-type", Some(bookmark)) => {
+                        bookmark.mime_type =
+                            event.attribute("type").unwrap_or_default().to_string();
+                    }
+                    ("application", Some(bookmark)) => {
+                        bookmark.applications.push(XbelApplication {
+                            name: event.attribute("name").unwrap_or_default().to_string(),
+                            exec: event.attribute("exec").unwrap_or_default().to_string(),
+                            // Files from before GLib 2.66 carry "timestamp" instead
+                            modified: timestamp("modified").or_else(|| timestamp("timestamp")),
+                            count: event
+                                .attribute("count")
+                                .and_then(|count| count.trim().parse().ok())
+                                .unwrap_or(1),
+                        });
+                    }
+                    ("private", Some(bookmark)) => bookmark.private = true,
+                    _ => {}
+                }
+                open.push(name.to_string());
+                text.clear();
+            }
+            XmlEvent::Text(content) => text.push_str(content),
+            XmlEvent::End { name } => {
+                open.pop();
+                let in_bookmark = open.last().is_some_and(|parent| parent == "bookmark");
+                match (local_name(name), current.as_mut()) {
+                    ("title", Some(bookmark)) if in_bookmark => {
+                        bookmark.title = Some(text.trim().to_string());
+                    }
+                    ("desc", Some(bookmark)) if in_bookmark => {
+                        bookmark.description = Some(text.trim().to_string());
+                    }
+                    ("group", Some(bookmark)) => bookmark.groups.push(text.trim().to_string()),
+                    ("bookmark", Some(_)) => {
+                        if let Some(bookmark) = current.take().filter(|b| !b.href.is_empty()) {
+                            document.bookmarks.push(bookmark);
+                        }
+                    }
+                    _ => {}
+                }
+                text.clear();
+            }
+        }
+    }
+
+    // println!("DEBUG: Parsed {} XBEL bookmarks", document.bookmarks.len());
+    Ok(document)
+}
+
+/// Record that `file` was opened with an application, as GTK's RecentManager does:
+/// the bookmark is added or updated and the application's count goes up.
+/// The store is replaced atomically, so readers never see a partial file, and
+/// everything in it the launcher does not use is written back as it was.
+pub fn record_opened(
+    xbel_path: &Path,
+    file: &Path,
+    mime_type: &str,
+    app_name: &str,
+    app_exec: &str,
+    now: u64,
+) -> Result<(), Box<dyn Error>> {
+    let mut document = match fs::read_to_string(xbel_path) {
+        // Better to record nothing than to overwrite a store we do not understand
+        Ok(content) => parse_xbel_document(&content)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => XbelDocument::default(),
+        Err(e) => return Err(e.into()),
+    };
+    let bookmarks = &mut document.bookmarks;
+
+    let href = file_uri(file);
+    let index = match bookmarks.iter().position(|bookmark| bookmark.href == href) {
+        Some(index) => index,
+        None => {
+            bookmarks.push(XbelBookmark {
+                href,
+                added: Some(now),
+                ..Default::default()
+            });
+            bookmarks.len() - 1
+        }
+    };
+    let bookmark = &mut bookmarks[index];
+    bookmark.modified = Some(now);
+    bookmark.visited = Some(now);
+    if !mime_type.is_empty() {
+        bookmark.mime_type = mime_type.to_string();
+    }
+
+    // GLib stores the command line shell-quoted as a whole
+    let exec = format!("'{}'", app_exec.replace('\'', "'\\''"));
+    match bookmark
+        .applications
+        .iter_mut()
+        .find(|app| app.name == app_name)
+    {
+        Some(app) => {
+            app.exec = exec;
+            app.modified = Some(now);
+            app.count += 1;
+        }
+        None => bookmark.applications.push(XbelApplication {
+            name: app_name.to_string(),
+            exec,
+            modified: Some(now),
+            count: 1,
+        }),
+    }
+
+    // A store that is a symlink (e.g. into a dotfiles repository) stays one
+    let target = fs::canonicalize(xbel_path).unwrap_or_else(|_| xbel_path.to_path_buf());
+    let dir = target.parent().ok_or("XBEL path has no parent directory")?;
+    fs::create_dir_all(dir)?;
+    let mut temp = tempfile::NamedTempFile::new_in(dir)?;
+    temp.write_all(write_xbel(&document).as_bytes())?;
+    temp.persist(&target)?;
+    Ok(())
+}
+
+/// Serialize a document in the layout GLib writes
+pub fn write_xbel(document: &XbelDocument) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <xbel version=\"1.0\"\n      \
+         xmlns:bookmark=\"http://www.freedesktop.org/standards/desktop-bookmarks\"\n      \
+         xmlns:mime=\"http://www.freedesktop.org/standards/shared-mime-info\"",
+    );
+    for (attribute, value) in &document.attributes {
+        if !["version", "xmlns:bookmark", "xmlns:mime"].contains(&attribute.as_str()) {
+            out.push_str(&format!("\n      {}=\"{}\"", attribute, escape(value)));
+        }
+    }
+    out.push_str("\n>\n");
+    for element in &document.other_elements {
+        out.push_str(&format!("  {}\n", element));
+    }
+
+    for bookmark in &document.bookmarks {
+        out.push_str(&format!("  <bookmark href=\"{}\"", escape(&bookmark.href)));
+        for (attribute, timestamp) in [
+            ("added", bookmark.added),
+            ("modified", bookmark.modified),
+            ("visited", bookmark.visited),
+        ] {
+            if let Some(timestamp) = timestamp {
+                out.push_str(&format!(
+                    " {}=\"{}\"",
+                    attribute,
+                    format_timestamp(timestamp)
+                ));
+            }
+        }
+        out.push_str(">\n");
+
+        if let Some(title) = &bookmark.title {
+            out.push_str(&format!("    <title>{}</title>\n", escape(title)));
+        }
+        if let Some(description) = &bookmark.description {
+            out.push_str(&format!("    <desc>{}</desc>\n", escape(description)));
+        }
+        for element in &bookmark.other_elements {
+            out.push_str(&format!("    {}\n", element));
+        }
+
+        out.push_str(&format!(
+            "    <info>\n      <metadata owner=\"{}\">\n",
+            FREEDESKTOP_OWNER
+        ));
+        if !bookmark.mime_type.is_empty() {
+            out.push_str(&format!(
+                "        <mime:mime-type type=\"{}\"/>\n",
+                escape(&bookmark.mime_type)
+            ));
+        }
+        if !bookmark.groups.is_empty() {
+            out.push_str("        <bookmark:groups>\n");
+            for group in &bookmark.groups {
+                out.push_str(&format!(
+                    "          <bookmark:group>{}</bookmark:group>\n",
+                    escape(group)
+                ));
+            }
+            out.push_str("        </bookmark:groups>\n");
+        }
+        if !bookmark.applications.is_empty() {
+            out.push_str("        <bookmark:applications>\n");
+            for app in &bookmark.applications {
+                out.push_str(&format!(
+                    "          <bookmark:application name=\"{}\" exec=\"{}\"",
+                    escape(&app.name),
+                    escape(&app.exec)
+                ));
+                if let Some(modified) = app.modified {
+                    out.push_str(&format!(" modified=\"{}\"", format_timestamp(modified)));
+                }
+                out.push_str(&format!(" count=\"{}\"/>\n", app.count));
+            }
+            out.push_str("        </bookmark:applications>\n");
+        }
+        if bookmark.private {
+            out.push_str("        <bookmark:private/>\n");
+        }
+        for field in &bookmark.other_fields {
+            out.push_str(&format!("        {}\n", field));
+        }
+        out.push_str("      </metadata>\n");
+        for metadata in &bookmark.other_metadata {
+            out.push_str(&format!("      {}\n", metadata));
+        }
+        out.push_str("    </info>\n  </bookmark>\n");
+    }
+
+    out.push_str("</xbel>\n");
+    out
+}
+
+/// Format seconds since the Unix epoch as "2024-03-05T10:20:30Z"
+pub fn format_timestamp(seconds: u64) -> String {
+    let seconds = seconds as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(DAY));
+    let time = seconds.rem_euclid(DAY);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Parse an ISO 8601 timestamp such as "2024-03-05T10:20:30.123456Z" (or with a
+/// "+01:00" offset) into seconds since the Unix epoch. Plain numbers are taken
+/// as seconds, as in old XBEL files.
+pub fn parse_timestamp(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        return value.parse().ok();
+    }
+
+    let (date, time) = value.split_once('T')?;
+    let mut date_parts = date.splitn(3, '-');
+    let year: i64 = date_parts.next()?.parse().ok()?;
+    let month: u32 = date_parts.next()?.parse().ok()?;
+    let day: u32 = date_parts.next()?.parse().ok()?;
+
+    let (clock, offset) = match time.strip_suffix('Z') {
+        Some(clock) => (clock, 0),
+        None => {
+            let sign_at = time.rfind(['+', '-'])?;
+            let (hours, minutes) = time[sign_at + 1..].split_once(':')?;
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            let sign = if time[sign_at..].starts_with('-') {
+                -1
+            } else {
+                1
+            };
+            (&time[..sign_at], sign * offset)
+        }
+    };
+    // Fractions of a second are dropped
+    let clock = clock.split('.').next()?;
+    let mut clock_parts = clock.splitn(3, ':');
+    let hour: i64 = clock_parts.next()?.parse().ok()?;
+    let minute: i64 = clock_parts.next()?.parse().ok()?;
+    let second: i64 = clock_parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let seconds =
+        days_from_civil(year, month, day) * DAY + hour * 3600 + minute * 60 + second - offset;
+    u64::try_from(seconds).ok()
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar
+/// (Howard Hinnant's days_from_civil)
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date of a day counted from 1970-01-01 (Howard Hinnant's civil_from_days)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info"
+>
+  <bookmark href="file:///home/user/My%20Files/na%C3%AFve%20%26%20co.txt" added="2024-03-05T10:20:30.123456Z"
+            modified="2024-03-06T08:00:00Z" visited="2024-03-05T10:20:30Z">
+    <title>Notes &amp; ideas</title>
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="text/plain"/>
+        <bookmark:groups>
+          <bookmark:group>gedit</bookmark:group>
+        </bookmark:groups>
+        <bookmark:applications>
+          <bookmark:application name="gedit" exec="&apos;gedit %u&apos;"
+                                modified="2024-03-07T12:00:00Z" count="3"/>
+          <bookmark:application name="Old App" exec="&apos;old %u&apos;" timestamp="1700000000"/>
+        </bookmark:applications>
+        <bookmark:private/>
+      </metadata>
+    </info>
+  </bookmark>
+  <bookmark href="https://example.com/page" added="2024-01-01T00:00:00Z"/>
+</xbel>
+"#;
+
+    #[test]
+    fn test_parse_full_bookmark() {
+        let bookmarks = parse_xbel(SAMPLE).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+
+        let notes = &bookmarks[0];
+        assert_eq!(
+            notes.file_path(),
+            Some(PathBuf::from("/home/user/My Files/naïve & co.txt"))
+        );
+        assert_eq!(notes.title.as_deref(), Some("Notes & ideas"));
+        assert_eq!(notes.mime_type, "text/plain");
+        assert_eq!(notes.groups, vec!["gedit"]);
+        assert!(notes.private);
+        assert_eq!(notes.added, parse_timestamp("2024-03-05T10:20:30Z"));
+        assert_eq!(notes.applications.len(), 2);
+        assert_eq!(notes.applications[0].name, "gedit");
+        assert_eq!(notes.applications[0].exec, "'gedit %u'");
+        assert_eq!(notes.applications[0].count, 3);
+        assert_eq!(notes.applications[1].modified, Some(1_700_000_000));
+        assert_eq!(notes.applications[1].count, 1);
+        assert_eq!(notes.last_used(), parse_timestamp("2024-03-07T12:00:00Z"));
+
+        // Bookmarks for other URI schemes are kept but have no local path
+        assert_eq!(bookmarks[1].file_path(), None);
+    }
+
+    #[test]
+    fn test_parse_timestamps() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2000-03-01T00:00:00Z"), Some(951_868_800));
+        assert_eq!(
+            parse_timestamp("2024-02-29T23:59:59.999999Z"),
+            Some(1_709_251_199)
+        );
+        assert_eq!(
+            parse_timestamp("2024-03-01T01:00:00+01:00"),
+            Some(1_709_251_200)
+        );
+        assert_eq!(
+            parse_timestamp("2024-02-29T23:00:00-01:00"),
+            Some(1_709_251_200)
+        );
+        assert_eq!(parse_timestamp("1700000000"), Some(1_700_000_000));
+        assert_eq!(parse_timestamp("2024-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+        assert_eq!(parse_timestamp(""), None);
+    }
+
+    #[test]
+    fn test_format_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_709_251_199), "2024-02-29T23:59:59Z");
+        for seconds in [951_868_800, 1_700_000_000, 4_102_444_800] {
+            assert_eq!(parse_timestamp(&format_timestamp(seconds)), Some(seconds));
+        }
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let document = parse_xbel_document(SAMPLE).unwrap();
+        assert_eq!(
+            parse_xbel_document(&write_xbel(&document)).unwrap(),
+            document
+        );
+    }
+
+    #[test]
+    fn test_record_opened_keeps_unknown_parts() {
+        const OTHER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info"
+      xmlns:kde="http://www.kde.org"
+>
+  <info><metadata owner="http://www.kde.org"><kde:version>2</kde:version></metadata></info>
+  <bookmark href="file:///home/user/photo.png">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="image/png"/>
+        <bookmark:icon type="theme" name="image-x-generic"/>
+      </metadata>
+      <metadata owner="http://www.kde.org">
+        <kde:rating stars="4">&lt;good&gt;</kde:rating>
+      </metadata>
+    </info>
+  </bookmark>
+</xbel>
+"#;
+        let dir = tempfile::tempdir().unwrap();
+        let store = dir.path().join("store.xbel");
+        fs::write(&store, OTHER).unwrap();
+        // The store is reached through a symlink, which must survive the rewrite
+        let xbel_path = dir.path().join("recently-used.xbel");
+        std::os::unix::fs::symlink(&store, &xbel_path).unwrap();
+
+        let photo = Path::new("/home/user/photo.png");
+        record_opened(&xbel_path, photo, "image/png", "Viewer", "eog %U", 100).unwrap();
+        assert!(fs::symlink_metadata(&xbel_path).unwrap().is_symlink());
+
+        let document = parse_xbel_document(&fs::read_to_string(&store).unwrap()).unwrap();
+        assert!(document
+            .attributes
+            .contains(&("xmlns:kde".to_string(), "http://www.kde.org".to_string())));
+        assert_eq!(
+            document.other_elements,
+            vec![
+                r#"<info><metadata owner="http://www.kde.org"><kde:version>2</kde:version></metadata></info>"#
+            ]
+        );
+        let bookmark = &document.bookmarks[0];
+        assert_eq!(bookmark.applications[0].name, "Viewer");
+        assert_eq!(
+            bookmark.other_fields,
+            vec![r#"<bookmark:icon type="theme" name="image-x-generic"/>"#]
+        );
+        assert_eq!(bookmark.other_metadata.len(), 1);
+        assert!(bookmark.other_metadata[0]
+            .contains(r#"<kde:rating stars="4">&lt;good&gt;</kde:rating>"#));
+    }
+
+    #[test]
+    fn test_record_opened() {
+        let dir = tempfile::tempdir().unwrap();
+        let xbel_path = dir.path().join("recently-used.xbel");
+        fs::write(&xbel_path, SAMPLE).unwrap();
+        let file = Path::new("/home/user/it's new.txt");
+
+        record_opened(
+            &xbel_path,
+            file,
+            "text/plain",
+            "Text Editor",
+            "gedit %U",
+            100,
+        )
+        .unwrap();
+        record_opened(
+            &xbel_path,
+            file,
+            "text/plain",
+            "Text Editor",
+            "gedit %U",
+            200,
+        )
+        .unwrap();
+
+        let bookmarks = parse_xbel(&fs::read_to_string(&xbel_path).unwrap()).unwrap();
+        // Existing bookmarks are kept as they were
+        assert_eq!(bookmarks.len(), 3);
+        assert_eq!(bookmarks[0].title.as_deref(), Some("Notes & ideas"));
+
+        let recorded = &bookmarks[2];
+        assert_eq!(recorded.file_path().as_deref(), Some(file));
+        assert_eq!(recorded.mime_type, "text/plain");
+        assert_eq!(recorded.added, Some(100));
+        assert_eq!(recorded.modified, Some(200));
+        assert_eq!(recorded.applications.len(), 1);
+        assert_eq!(recorded.applications[0].name, "Text Editor");
+        assert_eq!(recorded.applications[0].exec, "'gedit %U'");
+        assert_eq!(recorded.applications[0].count, 2);
+    }
+
+    #[test]
+    fn test_record_opened_keeps_unparsable_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let xbel_path = dir.path().join("recently-used.xbel");
+        fs::write(&xbel_path, "<xbel><bookmark>").unwrap();
+        assert!(record_opened(&xbel_path, Path::new("/a"), "", "App", "app %u", 1).is_err());
+        assert_eq!(fs::read_to_string(&xbel_path).unwrap(), "<xbel><bookmark>");
+
+        // A missing store is created
+        let new_path = dir.path().join("new/recently-used.xbel");
+        record_opened(&new_path, Path::new("/a"), "", "App", "app %u", 1).unwrap();
+        assert_eq!(
+            parse_xbel(&fs::read_to_string(&new_path).unwrap())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_malformed_xbel_is_an_error() {
+        assert!(parse_xbel("<xbel><bookmark href=\"file:///a\"></xbel>").is_err());
+    }
+}
@@ -0,0 +1,149 @@
+// xdg.rs - XDG Base Directory lookups
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Default for XDG_DATA_DIRS when the variable is unset or empty
+const DEFAULT_DATA_DIRS: &str = "/usr/local/share:/usr/share";
+
+/// Default for XDG_CONFIG_DIRS when the variable is unset or empty
+const DEFAULT_CONFIG_DIRS: &str = "/etc/xdg";
+
+/// The user's Flatpak exports, which flatpak ranks above the system data directories
+const USER_EXPORT_DIR: &str = "~/.local/share/flatpak/exports/share";
+
+/// Well-known system export locations that are not always present in XDG_DATA_DIRS
+/// when the launcher is started from a minimal session environment
+const EXTRA_DATA_DIRS: &[&str] = &[
+    "/var/lib/flatpak/exports/share",
+    "/var/lib/snapd/desktop",
+];
+
+pub fn home_dir() -> PathBuf {
+    PathBuf::from(env::var("HOME").unwrap_or_default())
+}
+
+/// $XDG_DATA_HOME, defaulting to ~/.local/share
+pub fn data_home() -> PathBuf {
+    base_dir_from(env::var("XDG_DATA_HOME").ok(), ".local/share")
+}
+
+/// $XDG_CACHE_HOME, defaulting to ~/.cache
+pub fn cache_home() -> PathBuf {
+    base_dir_from(env::var("XDG_CACHE_HOME").ok(), ".cache")
+}
+
+/// $XDG_CONFIG_HOME, defaulting to ~/.config
+pub fn config_home() -> PathBuf {
+    base_dir_from(env::var("XDG_CONFIG_HOME").ok(), ".config")
+}
+
+/// $XDG_CONFIG_DIRS in order of preference, defaulting to /etc/xdg
+pub fn config_dirs() -> Vec<PathBuf> {
+    dir_list_from(env::var("XDG_CONFIG_DIRS").ok(), DEFAULT_CONFIG_DIRS)
+}
+
+/// $XDG_DATA_DIRS in order of preference, defaulting to /usr/local/share:/usr/share
+pub fn data_dirs() -> Vec<PathBuf> {
+    dir_list_from(env::var("XDG_DATA_DIRS").ok(), DEFAULT_DATA_DIRS)
+}
+
+/// All data directories in order of precedence: the user's data home and Flatpak
+/// exports first, then the system directories, then any known system export
+/// directories not already listed
+pub fn data_search_path() -> Vec<PathBuf> {
+    search_path_from(data_home(), data_dirs())
+}
+
+fn search_path_from(data_home: PathBuf, data_dirs: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut dirs = vec![data_home, expand_home(USER_EXPORT_DIR)];
+    dirs.extend(data_dirs);
+    dirs.extend(EXTRA_DATA_DIRS.iter().map(|dir| expand_home(dir)));
+
+    let mut unique: Vec<PathBuf> = Vec::new();
+    for dir in dirs {
+        if !unique.contains(&dir) {
+            unique.push(dir);
+        }
+    }
+    unique
+}
+
+/// The desktop environments named in $XDG_CURRENT_DESKTOP (e.g. "ubuntu:GNOME")
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix("~/") {
+        Some(rest) => home_dir().join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Relative or empty values are invalid per the spec and fall back to the default
+fn base_dir_from(value: Option<String>, default_relative: &str) -> PathBuf {
+    match value {
+        Some(value) if Path::new(&value).is_absolute() => PathBuf::from(value),
+        _ => home_dir().join(default_relative),
+    }
+}
+
+fn dir_list_from(value: Option<String>, default: &str) -> Vec<PathBuf> {
+    let value = value.filter(|v| !v.trim().is_empty());
+    value
+        .as_deref()
+        .unwrap_or(default)
+        .split(':')
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dir_list_parsing() {
+        assert_eq!(
+            dir_list_from(Some("/a:relative::/b/".to_string()), DEFAULT_DATA_DIRS),
+            vec![PathBuf::from("/a"), PathBuf::from("/b/")]
+        );
+        assert_eq!(
+            dir_list_from(Some(String::new()), DEFAULT_DATA_DIRS),
+            vec![PathBuf::from("/usr/local/share"), PathBuf::from("/usr/share")]
+        );
+        assert_eq!(dir_list_from(None, DEFAULT_DATA_DIRS).len(), 2);
+    }
+
+    #[test]
+    fn test_user_exports_rank_above_system_dirs() {
+        let user_exports = expand_home(USER_EXPORT_DIR);
+        let system_exports = PathBuf::from("/var/lib/flatpak/exports/share");
+        let dirs = search_path_from(
+            PathBuf::from("/home/u/.local/share"),
+            vec![PathBuf::from("/usr/share"), system_exports.clone()],
+        );
+        assert_eq!(dirs[0], PathBuf::from("/home/u/.local/share"));
+        assert_eq!(dirs[1], user_exports);
+        assert_eq!(dirs[2], PathBuf::from("/usr/share"));
+        // Already listed directories keep their place
+        assert_eq!(dirs[3], system_exports);
+        assert_eq!(dirs.iter().filter(|dir| **dir == system_exports).count(), 1);
+    }
+
+    #[test]
+    fn test_base_dir_rejects_relative_paths() {
+        assert_eq!(
+            base_dir_from(Some("/custom/data".to_string()), ".local/share"),
+            PathBuf::from("/custom/data")
+        );
+        assert!(base_dir_from(Some("relative".to_string()), ".local/share")
+            .ends_with(".local/share"));
+    }
+}
@@ -0,0 +1,298 @@
+// xml.rs - Minimal non-validating XML reader
+//
+// Enough XML for the freedesktop files the launcher reads: elements, attributes
+// in any quoting and layout, character and entity references, CDATA, comments,
+// processing instructions and a DOCTYPE (which is skipped). Namespace prefixes
+// are kept as part of the element name.
+use std::error::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum XmlEvent {
+    /// An opening tag; a self-closing tag is followed by a matching End
+    Start {
+        name: String,
+        attributes: Vec<(String, String)>,
+    },
+    End {
+        name: String,
+    },
+    /// Character data with references resolved, whitespace included
+    Text(String),
+}
+
+impl XmlEvent {
+    /// The value of `attribute` on a Start event
+    pub fn attribute(&self, attribute: &str) -> Option<&str> {
+        match self {
+            XmlEvent::Start { attributes, .. } => attributes
+                .iter()
+                .find(|(name, _)| name == attribute)
+                .map(|(_, value)| value.as_str()),
+            _ => None,
+        }
+    }
+}
+
+/// The name without its namespace prefix, e.g. "mime-type" for "mime:mime-type"
+pub fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Read a whole document into events, checking that tags are properly nested
+pub fn parse(input: &str) -> Result<Vec<XmlEvent>, Box<dyn Error>> {
+    let mut events = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        let offset = input.len() - rest.len();
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or("Unterminated comment")?;
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>").ok_or("Unterminated CDATA section")?;
+            events.push(XmlEvent::Text(after[..end].to_string()));
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            let end = after
+                .find("?>")
+                .ok_or("Unterminated processing instruction")?;
+            rest = &after[end + 2..];
+        } else if rest.starts_with("<!") {
+            rest = skip_declaration(rest).ok_or("Unterminated declaration")?;
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').ok_or("Unterminated end tag")?;
+            let name = after[..end].trim().to_string();
+            match open.pop() {
+                Some(expected) if expected == name => {}
+                _ => return Err(format!("Unexpected </{}> at byte {}", name, offset).into()),
+            }
+            events.push(XmlEvent::End { name });
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (name, attributes, self_closing, after) =
+                parse_tag(after).map_err(|e| format!("{} at byte {}", e, offset))?;
+            events.push(XmlEvent::Start {
+                name: name.clone(),
+                attributes,
+            });
+            if self_closing {
+                events.push(XmlEvent::End { name });
+            } else {
+                open.push(name);
+            }
+            rest = after;
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            events.push(XmlEvent::Text(unescape(&rest[..end])?));
+            rest = &rest[end..];
+        }
+    }
+
+    match open.pop() {
+        Some(name) => Err(format!("Element <{}> is not closed", name).into()),
+        None => Ok(events),
+    }
+}
+
+/// Skip a <!DOCTYPE ...> (or similar) declaration, including an internal subset
+fn skip_declaration(input: &str) -> Option<&str> {
+    let mut depth = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            '>' if depth == 0 => return Some(&input[i + 1..]),
+            _ => {}
+        }
+    }
+    None
+}
+
+type Tag<'a> = (String, Vec<(String, String)>, bool, &'a str);
+
+/// Parse a tag after its '<': name, attributes, whether it is self-closing and the rest
+fn parse_tag(input: &str) -> Result<Tag<'_>, Box<dyn Error>> {
+    let name_end = input
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .ok_or("Unterminated tag")?;
+    let name = input[..name_end].to_string();
+    if name.is_empty() {
+        return Err("Missing element name".into());
+    }
+
+    let mut attributes = Vec::new();
+    let mut rest = &input[name_end..];
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return Ok((name, attributes, true, after));
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return Ok((name, attributes, false, after));
+        }
+
+        let eq = rest.find('=').ok_or("Attribute without value")?;
+        let attribute = rest[..eq].trim().to_string();
+        if attribute.is_empty() || attribute.contains(|c: char| c.is_whitespace() || c == '>') {
+            return Err("Malformed attribute".into());
+        }
+        rest = rest[eq + 1..].trim_start();
+        let quote = rest
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or("Unquoted attribute value")?;
+        let value_end = rest[1..]
+            .find(quote)
+            .ok_or("Unterminated attribute value")?;
+        attributes.push((attribute, unescape(&rest[1..1 + value_end])?));
+        rest = &rest[value_end + 2..];
+    }
+}
+
+/// Resolve the predefined entities and character references
+pub fn unescape(text: &str) -> Result<String, Box<dyn Error>> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or("Unterminated entity reference")?;
+        let entity = &rest[start + 1..start + end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16).ok()
+                } else if let Some(decimal) = entity.strip_prefix('#') {
+                    decimal.parse().ok()
+                } else {
+                    None
+                };
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| format!("Unknown entity &{};", entity))?
+            }
+        };
+        unescaped.push(c);
+        rest = &rest[start + end + 1..];
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+/// Escape text for use in element content or a double- or single-quoted attribute
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Write events from `parse` back as XML. An element without content is written
+/// as a self-closing tag.
+pub fn write(events: &[XmlEvent]) -> String {
+    let mut out = String::new();
+    let mut events = events.iter().peekable();
+    while let Some(event) = events.next() {
+        match event {
+            XmlEvent::Start { name, attributes } => {
+                out.push('<');
+                out.push_str(name);
+                for (attribute, value) in attributes {
+                    out.push_str(&format!(" {}=\"{}\"", attribute, escape(value)));
+                }
+                if matches!(events.peek(), Some(XmlEvent::End { .. })) {
+                    events.next();
+                    out.push_str("/>");
+                } else {
+                    out.push('>');
+                }
+            }
+            XmlEvent::End { name } => out.push_str(&format!("</{}>", name)),
+            XmlEvent::Text(text) => out.push_str(&escape(text)),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start(name: &str, attributes: &[(&str, &str)]) -> XmlEvent {
+        XmlEvent::Start {
+            name: name.to_string(),
+            attributes: attributes
+                .iter()
+                .map(|(n, v)| (n.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    fn end(name: &str) -> XmlEvent {
+        XmlEvent::End {
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_elements_attributes_and_text() {
+        let events = parse(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE xbel [ <!ENTITY x \"y\"> ]>\n<!-- note -->\
+             <a\n  href='x&amp;y'\n  n = \"1\"><b/>T&lt;&#65;&#x42;<![CDATA[<raw>]]></a>",
+        )
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                XmlEvent::Text("\n".to_string()),
+                XmlEvent::Text("\n".to_string()),
+                start("a", &[("href", "x&y"), ("n", "1")]),
+                start("b", &[]),
+                end("b"),
+                XmlEvent::Text("T<AB".to_string()),
+                XmlEvent::Text("<raw>".to_string()),
+                end("a"),
+            ]
+        );
+        assert_eq!(events[2].attribute("href"), Some("x&y"));
+        assert_eq!(local_name("mime:mime-type"), "mime-type");
+        assert_eq!(local_name("title"), "title");
+    }
+
+    #[test]
+    fn test_malformed_documents() {
+        assert!(parse("<a><b></a></b>").is_err());
+        assert!(parse("<a>").is_err());
+        assert!(parse("<a x=1/>").is_err());
+        assert!(parse("<a>&bogus;</a>").is_err());
+        assert!(parse("<a x=\"1></a>").is_err());
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let xml = "<kde:meta owner=\"x &amp; y\"><kde:flag/>Tom&apos;s<b>1</b></kde:meta>";
+        let events = parse(xml).unwrap();
+        assert_eq!(write(&events), xml);
+    }
+
+    #[test]
+    fn test_escape_round_trip() {
+        let text = "Tom & Jerry's <\"best\">";
+        assert_eq!(unescape(&escape(text)).unwrap(), text);
+    }
+}